@@ -0,0 +1,6 @@
+//! Request and response bodies
+//!
+//! This re-exports [`Body`], and adds [`BodyReader`] to read a body with
+//! [`std::io::BufRead`].
+
+pub use crate::lunatic_impl::body::{Body, BodyReader};
@@ -3,6 +3,7 @@ use http::header::HeaderValue;
 use http::uri::{Authority, Scheme};
 use http::Uri;
 use lunatic::net::TcpStream;
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
@@ -11,7 +12,10 @@ use std::time::Duration;
 
 #[cfg(feature = "trust-dns")]
 use crate::dns::TrustDnsResolver;
-use crate::proxy::Proxy;
+use crate::into_url::expect_uri;
+use crate::lunatic_impl::HttpStream;
+use crate::proxy::{Proxy, ProxyScheme};
+use crate::Url;
 
 #[derive(Clone)]
 pub(crate) enum HttpConnector {
@@ -33,6 +37,16 @@ impl HttpConnector {
         Self::GaiWithDnsOverrides(overridden_resolver)
     }
 
+    /// The addresses `host` resolves to according to the DNS overrides.
+    fn overrides(&self, host: &str) -> Option<&[SocketAddr]> {
+        match self {
+            HttpConnector::GaiWithDnsOverrides(resolver) => {
+                resolver.overrides.get(host).map(Vec::as_slice)
+            }
+            _ => None,
+        }
+    }
+
     // pub fn set_keepalive(&mut self, timeout) {
     //     match self {
     //         Htt
@@ -86,6 +100,19 @@ enum Inner {
     },
 }
 
+impl Inner {
+    fn http(&self) -> &HttpConnector {
+        match self {
+            #[cfg(not(feature = "__tls"))]
+            Inner::Http(http) => http,
+            #[cfg(feature = "default-tls")]
+            Inner::DefaultTls(http, _) => http,
+            #[cfg(feature = "__rustls")]
+            Inner::RustlsTls { http, .. } => http,
+        }
+    }
+}
+
 impl Connector {
     #[cfg(not(feature = "__tls"))]
     pub(crate) fn new<T>(
@@ -193,6 +220,86 @@ impl Connector {
         self.timeout = timeout;
     }
 
+    pub(crate) fn set_proxies(&mut self, proxies: Arc<Vec<Proxy>>) {
+        self.proxies = proxies;
+    }
+
+    /// Open a connection for a request to `url`, without sending anything.
+    ///
+    /// `http` urls are connected through the HTTP proxy for them, if any, and
+    /// `https` urls over TLS. The connect timeout applies to plain TCP
+    /// connections.
+    pub(crate) fn connect(&self, url: &Url) -> crate::Result<HttpStream> {
+        self.target(url).connect()
+    }
+
+    /// Decide where to connect for a request to `url`, like
+    /// [`Connector::connect`] but without opening the connection.
+    pub(crate) fn target(&self, url: &Url) -> ConnectTarget {
+        let connect_to = self
+            .http_proxy(&expect_uri(url))
+            .unwrap_or_else(|| url.clone());
+
+        // TLS connections resolve the host themselves, so overrides only
+        // apply to plain TCP connections.
+        let mut addrs = None;
+        if connect_to.scheme() == "http" {
+            let port = connect_to.port_or_known_default().unwrap_or(80);
+            addrs = connect_to
+                .host_str()
+                .and_then(|host| self.inner.http().overrides(host))
+                .map(|addrs| {
+                    addrs
+                        .iter()
+                        .map(|addr| SocketAddr::new(addr.ip(), port))
+                        .collect()
+                });
+        }
+
+        ConnectTarget {
+            url: connect_to,
+            addrs,
+            timeout: self.timeout,
+        }
+    }
+
+    /// The addresses `host` resolves to, honoring the DNS overrides.
+    ///
+    /// Overridden addresses keep the port they were configured with, other
+    /// addresses have port 0.
+    pub(crate) fn resolve(&self, host: &str) -> crate::Result<Vec<SocketAddr>> {
+        if let Some(addrs) = self.inner.http().overrides(host) {
+            return Ok(addrs.to_vec());
+        }
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![SocketAddr::new(ip, 0)]);
+        }
+        lunatic::net::resolve(&format!("{}:0", host))
+            .map(Iterator::collect)
+            .map_err(crate::error::connect)
+    }
+
+    /// The url of the HTTP proxy to send a request for `dst` through, if any.
+    ///
+    /// Requests are only forwarded through HTTP proxies for `http`
+    /// destinations. `https` destinations would need a `CONNECT` tunnel
+    /// through the proxy, so they connect directly.
+    pub(crate) fn http_proxy(&self, dst: &Uri) -> Option<Url> {
+        if dst.scheme() != Some(&Scheme::HTTP) {
+            return None;
+        }
+
+        let proxy = self.proxies.iter().find_map(|p| p.intercept(dst))?;
+        let url = match proxy {
+            ProxyScheme::Http { host, .. } => format!("http://{}", host),
+            ProxyScheme::Https { host, .. } => format!("https://{}", host),
+            #[cfg(feature = "socks")]
+            ProxyScheme::Socks5 { .. } => return None,
+        };
+        Url::parse(&url).ok()
+    }
+
     // pub(crate) fn set_verbose(&mut self, enabled: bool) {
     //     self.verbose.0 = enabled;
     // }
@@ -416,6 +523,28 @@ impl Connector {
     }
 }
 
+/// Where to open a connection, as decided by [`Connector::target`].
+///
+/// Unlike the `Connector` it can be serialized, so the connection can be
+/// opened by another process.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ConnectTarget {
+    /// The url to connect to, either the request's or its proxy's.
+    url: Url,
+    /// The overridden addresses of the host of `url`, if any.
+    addrs: Option<Vec<SocketAddr>>,
+    timeout: Option<Duration>,
+}
+
+impl ConnectTarget {
+    pub(crate) fn connect(&self) -> crate::Result<HttpStream> {
+        match self.addrs {
+            Some(ref addrs) => HttpStream::connect_addrs(addrs, self.timeout),
+            None => HttpStream::connect_timeout(self.url.clone(), self.timeout),
+        }
+    }
+}
+
 fn into_uri(scheme: Scheme, host: Authority) -> Uri {
     // TODO: Should the `http` crate get `From<(Scheme, Authority)> for Uri`?
     http::Uri::builder()
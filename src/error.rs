@@ -3,6 +3,8 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::io;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::{StatusCode, Url};
 
 /// A `Result` alias where the `Err` case is `nightfly::Error`.
@@ -13,6 +15,10 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// Note: Errors may include the full URL used to make the `Request`. If the URL
 /// contains sensitive information (e.g. an API key as a query parameter), be
 /// sure to remove it ([`without_url`](Error::without_url))
+///
+/// An `Error` can be serialized to pass it to another lunatic process. It
+/// keeps its kind and URL, and whether it was a timeout, but its source is
+/// replaced by the source's message.
 pub struct Error {
     inner: Box<Inner>,
 }
@@ -26,19 +32,42 @@ struct Inner {
 }
 
 impl Error {
-    pub(crate) fn new<E>(kind: Kind, source: Option<E>) -> Error
-    where
-        E: Into<BoxError>,
-    {
+    // Every error has a `Kind`, and keeps the error that caused it as its
+    // `source`. Constructors must pass the original error along rather than
+    // a description of it.
+    pub(crate) fn new(kind: Kind, source: Option<BoxError>) -> Error {
         Error {
             inner: Box::new(Inner {
                 kind,
-                source: source.map(Into::into),
+                source,
                 url: None,
             }),
         }
     }
 
+    /// Creates an error for an HTTP error status code.
+    ///
+    /// This is mostly useful to compare against in tests, since errors are
+    /// equal when their [`kind`](Error::kind) and [`status`](Error::status)
+    /// match. `status` should be a client or server error status.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nightfly::{Error, StatusCode};
+    /// # fn run() -> Result<(), Error> {
+    /// let err = nightfly::get("https://httpbin.org/status/400")?
+    ///     .error_for_status()
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(err, Error::from_status(StatusCode::BAD_REQUEST));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_status(status: StatusCode) -> Error {
+        Error::new(Kind::Status(status), None)
+    }
+
     /// Returns a possible URL related to this error.
     ///
     /// # Examples
@@ -82,6 +111,41 @@ impl Error {
         self
     }
 
+    /// Returns the kind of this error.
+    ///
+    /// A request that timed out reports [`ErrorKind::Timeout`], even if it
+    /// timed out while connecting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nightfly::ErrorKind;
+    ///
+    /// # fn run() {
+    /// match nightfly::get("http://localhost:1") {
+    ///     Err(e) if e.kind() == ErrorKind::Connect => println!("server is down"),
+    ///     Err(e) => println!("other error: {}", e),
+    ///     Ok(res) => println!("status: {}", res.status()),
+    /// }
+    /// # }
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        if self.is_timeout() {
+            return ErrorKind::Timeout;
+        }
+
+        match self.inner.kind {
+            Kind::Builder => ErrorKind::Builder,
+            Kind::Request => ErrorKind::Request,
+            Kind::Connect => ErrorKind::Connect,
+            Kind::Redirect => ErrorKind::Redirect,
+            Kind::Status(_) => ErrorKind::Status,
+            Kind::Body => ErrorKind::Body,
+            Kind::Decode => ErrorKind::Decode,
+            Kind::Upgrade => ErrorKind::Upgrade,
+        }
+    }
+
     /// Returns true if the error is from a type Builder.
     pub fn is_builder(&self) -> bool {
         matches!(self.inner.kind, Kind::Builder)
@@ -116,22 +180,9 @@ impl Error {
         matches!(self.inner.kind, Kind::Request)
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
     /// Returns true if the error is related to connect
     pub fn is_connect(&self) -> bool {
-        let mut source = self.source();
-
-        while let Some(err) = source {
-            if let Some(hyper_err) = err.downcast_ref::<hyper::Error>() {
-                if hyper_err.is_connect() {
-                    return true;
-                }
-            }
-
-            source = err.source();
-        }
-
-        false
+        matches!(self.inner.kind, Kind::Connect)
     }
 
     /// Returns true if the error is related to the request or response body
@@ -154,6 +205,23 @@ impl Error {
 
     // private
 
+    // The source of an error can't be cloned, so the copy keeps the kind and
+    // url, and replaces the source with its message.
+    pub(crate) fn clone_lossy(&self) -> Error {
+        let source = self
+            .inner
+            .source
+            .as_ref()
+            .map(|e| BoxError::from(e.to_string()));
+        Error {
+            inner: Box::new(Inner {
+                kind: self.inner.kind,
+                source,
+                url: self.inner.url.clone(),
+            }),
+        }
+    }
+
     #[allow(unused)]
     pub(crate) fn into_io(self) -> io::Error {
         io::Error::new(io::ErrorKind::Other, self)
@@ -179,9 +247,13 @@ impl fmt::Debug for Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let timed_out = self.is_timeout();
+
         match self.inner.kind {
+            _ if timed_out => f.write_str("request timed out")?,
             Kind::Builder => f.write_str("builder error")?,
             Kind::Request => f.write_str("error sending request")?,
+            Kind::Connect => f.write_str("failed to connect")?,
             Kind::Body => f.write_str("request or response body error")?,
             Kind::Decode => f.write_str("error decoding response body")?,
             Kind::Redirect => f.write_str("error following redirect")?,
@@ -189,9 +261,10 @@ impl fmt::Display for Error {
             Kind::Status(ref code) => {
                 let prefix = if code.is_client_error() {
                     "HTTP status client error"
-                } else {
-                    debug_assert!(code.is_server_error());
+                } else if code.is_server_error() {
                     "HTTP status server error"
+                } else {
+                    "HTTP status error"
                 };
                 write!(f, "{} ({})", prefix, code)?;
             }
@@ -202,62 +275,176 @@ impl fmt::Display for Error {
         }
 
         if let Some(e) = &self.inner.source {
-            write!(f, ": {}", e)?;
+            // "request timed out: operation timed out" says the same thing twice.
+            if !(timed_out && e.is::<TimedOut>()) {
+                write!(f, ": {}", e)?;
+            }
         }
 
         Ok(())
     }
 }
 
+/// Errors are equal when they have the same [`kind`](Error::kind) and
+/// [`status`](Error::status). Their URL and source are not compared.
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        self.kind() == other.kind() && self.status() == other.status()
+    }
+}
+
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         self.inner.source.as_ref().map(|e| &**e as _)
     }
 }
 
-#[derive(Debug)]
+/// The kind of an [`Error`], as returned by [`Error::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// An error building a client or request.
+    Builder,
+    /// An error sending a request.
+    Request,
+    /// An error following a redirect.
+    Redirect,
+    /// An error status code, from `HttpResponse::error_for_status`.
+    Status,
+    /// An error reading or writing a request or response body.
+    Body,
+    /// An error decoding a response body.
+    Decode,
+    /// An error connecting to the server.
+    Connect,
+    /// The request timed out.
+    Timeout,
+    /// An error upgrading a connection.
+    Upgrade,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub(crate) enum Kind {
     Builder,
     Request,
+    Connect,
     Redirect,
-    Status(StatusCode),
+    Status(#[serde(with = "status_code")] StatusCode),
     Body,
     Decode,
     Upgrade,
 }
 
+/// A serializable form of `Error`, used to pass errors between lunatic
+/// processes.
+///
+/// Like `Error::clone_lossy`, it keeps the kind and url and replaces the
+/// source with its message. Whether the error was a timeout is kept as well.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SerializedError {
+    kind: Kind,
+    message: Option<String>,
+    timed_out: bool,
+    url: Option<Url>,
+}
+
+impl From<Error> for SerializedError {
+    fn from(err: Error) -> SerializedError {
+        SerializedError::from(&err)
+    }
+}
+
+impl<'a> From<&'a Error> for SerializedError {
+    fn from(err: &'a Error) -> SerializedError {
+        SerializedError {
+            timed_out: err.is_timeout(),
+            kind: err.inner.kind,
+            message: err.inner.source.as_ref().map(|e| e.to_string()),
+            url: err.inner.url.clone(),
+        }
+    }
+}
+
+impl From<SerializedError> for Error {
+    fn from(err: SerializedError) -> Error {
+        let source = if err.timed_out {
+            Some(BoxError::from(TimedOut))
+        } else {
+            err.message.map(BoxError::from)
+        };
+        Error {
+            inner: Box::new(Inner {
+                kind: err.kind,
+                source,
+                url: err.url,
+            }),
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        SerializedError::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Error {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Error, D::Error> {
+        SerializedError::deserialize(deserializer).map(Error::from)
+    }
+}
+
+mod status_code {
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::Serializer;
+
+    use crate::StatusCode;
+
+    pub(super) fn serialize<S: Serializer>(status: &StatusCode, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u16(status.as_u16())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<StatusCode, D::Error> {
+        StatusCode::from_u16(u16::deserialize(d)?).map_err(D::Error::custom)
+    }
+}
+
 // constructors
 
 pub(crate) fn builder<E: Into<BoxError>>(e: E) -> Error {
-    Error::new(Kind::Builder, Some(e))
+    Error::new(Kind::Builder, Some(e.into()))
 }
 
 pub(crate) fn body<E: Into<BoxError>>(e: E) -> Error {
-    Error::new(Kind::Body, Some(e))
+    Error::new(Kind::Body, Some(e.into()))
 }
 
 pub(crate) fn decode<E: Into<BoxError>>(e: E) -> Error {
-    Error::new(Kind::Decode, Some(e))
+    Error::new(Kind::Decode, Some(e.into()))
 }
 
 pub(crate) fn request<E: Into<BoxError>>(e: E) -> Error {
-    Error::new(Kind::Request, Some(e))
+    Error::new(Kind::Request, Some(e.into()))
+}
+
+pub(crate) fn connect<E: Into<BoxError>>(e: E) -> Error {
+    Error::new(Kind::Connect, Some(e.into()))
 }
 
 pub(crate) fn redirect<E: Into<BoxError>>(e: E, url: Url) -> Error {
-    Error::new(Kind::Redirect, Some(e)).with_url(url)
+    Error::new(Kind::Redirect, Some(e.into())).with_url(url)
 }
 
 pub(crate) fn status_code(url: Url, status: StatusCode) -> Error {
-    Error::new(Kind::Status(status), None::<Error>).with_url(url)
+    Error::new(Kind::Status(status), None).with_url(url)
 }
 
 pub(crate) fn url_bad_scheme(url: Url) -> Error {
-    Error::new(Kind::Builder, Some(BadScheme)).with_url(url)
+    Error::new(Kind::Builder, Some(BadScheme.into())).with_url(url)
 }
 
 pub(crate) fn upgrade<E: Into<BoxError>>(e: E) -> Error {
-    Error::new(Kind::Upgrade, Some(e))
+    Error::new(Kind::Upgrade, Some(e.into()))
 }
 
 // io::Error helpers
@@ -312,7 +499,7 @@ mod tests {
 
     #[test]
     fn test_source_chain() {
-        let root = Error::new(Kind::Request, None::<Error>);
+        let root = Error::new(Kind::Request, None);
         assert!(root.source().is_none());
 
         let link = super::body(root);
@@ -321,6 +508,84 @@ mod tests {
         assert_sync::<Error>();
     }
 
+    #[test]
+    fn serialized_error_keeps_kind() {
+        let roundtrip = |err: Error| {
+            let json = serde_json::to_vec(&err).unwrap();
+            serde_json::from_slice::<Error>(&json).unwrap()
+        };
+
+        let err =
+            roundtrip(super::connect(TimedOut).with_url("http://localhost/".parse().unwrap()));
+        assert!(err.is_connect());
+        assert!(err.is_timeout());
+        assert_eq!(err.url().map(Url::as_str), Some("http://localhost/"));
+
+        let err = roundtrip(Error::from_status(StatusCode::NOT_FOUND));
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+
+        let err = roundtrip(super::request("connection reset"));
+        assert!(err.is_request());
+        assert!(!err.is_timeout());
+        assert_eq!(err.source().unwrap().to_string(), "connection reset");
+    }
+
+    #[test]
+    fn source_is_the_stored_error() {
+        let orig = io::Error::new(io::ErrorKind::ConnectionReset, "reset");
+        let err = super::request(orig);
+
+        let source = err.source().expect("source");
+        let io = source.downcast_ref::<io::Error>().expect("io::Error");
+        assert_eq!(io.kind(), io::ErrorKind::ConnectionReset);
+    }
+
+    #[test]
+    fn kind() {
+        assert_eq!(super::builder("oops").kind(), ErrorKind::Builder);
+        assert_eq!(super::connect("refused").kind(), ErrorKind::Connect);
+        assert_eq!(super::request(TimedOut).kind(), ErrorKind::Timeout);
+        assert_eq!(super::connect(TimedOut).kind(), ErrorKind::Timeout);
+
+        let url = Url::parse("http://localhost/").unwrap();
+        let err = super::status_code(url, StatusCode::BAD_GATEWAY);
+        assert_eq!(err.kind(), ErrorKind::Status);
+    }
+
+    #[test]
+    fn partial_eq() {
+        let url = Url::parse("http://localhost/").unwrap();
+        let err = super::status_code(url, StatusCode::BAD_REQUEST);
+
+        assert_eq!(err, Error::from_status(StatusCode::BAD_REQUEST));
+        assert_ne!(err, Error::from_status(StatusCode::NOT_FOUND));
+        assert_ne!(err, super::builder("oops"));
+        assert_eq!(super::request("a"), super::request("b"));
+    }
+
+    #[test]
+    fn display() {
+        let url = Url::parse("http://localhost/").unwrap();
+
+        let err = super::status_code(url.clone(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            err.to_string(),
+            "HTTP status client error (404 Not Found) for url (http://localhost/)"
+        );
+
+        let err = super::connect("connection refused").with_url(url.clone());
+        assert_eq!(
+            err.to_string(),
+            "failed to connect for url (http://localhost/): connection refused"
+        );
+
+        let err = super::request(TimedOut).with_url(url);
+        assert_eq!(
+            err.to_string(),
+            "request timed out for url (http://localhost/)"
+        );
+    }
+
     #[test]
     fn mem_size_of() {
         use std::mem::size_of;
@@ -0,0 +1,236 @@
+//! HTTP header types
+//!
+//! This re-exports everything from [`http::header`], and adds typed headers
+//! that can be read from a [`HeaderMap`] with [`HeaderExt::typed_get`].
+
+use std::time::{Duration, SystemTime};
+
+use mime::Mime;
+
+use crate::util::base64_encode;
+pub use http::header::*;
+
+/// A header that can be parsed from its [`HeaderValue`].
+pub trait TypedHeader: Sized {
+    /// The name of the header.
+    fn name() -> HeaderName;
+
+    /// Parse the header from its value, returning `None` if it is invalid.
+    fn decode(value: &HeaderValue) -> Option<Self>;
+}
+
+/// Extension trait for reading typed headers from a [`HeaderMap`].
+pub trait HeaderExt {
+    /// Get the first value of the header `H`, parsed.
+    ///
+    /// Returns `None` if the header is absent or can't be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use nightfly::header::{ContentLength, HeaderExt, HeaderMap, HeaderValue, CONTENT_LENGTH};
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(CONTENT_LENGTH, HeaderValue::from_static("42"));
+    ///
+    /// assert_eq!(headers.typed_get::<ContentLength>(), Some(ContentLength(42)));
+    /// ```
+    fn typed_get<H: TypedHeader>(&self) -> Option<H>;
+}
+
+impl HeaderExt for HeaderMap {
+    fn typed_get<H: TypedHeader>(&self) -> Option<H> {
+        self.get(H::name()).and_then(H::decode)
+    }
+}
+
+/// The `Content-Length` header, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContentLength(pub u64);
+
+impl TypedHeader for ContentLength {
+    fn name() -> HeaderName {
+        CONTENT_LENGTH
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        value.to_str().ok()?.trim().parse().ok().map(ContentLength)
+    }
+}
+
+/// The `Content-Type` header.
+///
+/// It also has constants for common `Content-Type` values, which can be used
+/// with
+/// [`RequestBuilder::body_with_content_type`](crate::RequestBuilder::body_with_content_type).
+///
+/// # Example
+///
+/// ```rust
+/// use nightfly::header::ContentType;
+///
+/// # fn run() -> Result<(), nightfly::Error> {
+/// let client = nightfly::Client::new();
+/// let res = client.post("http://httpbin.org/post")
+///     .body_with_content_type(r#"{"lang":"rust"}"#.to_string(), ContentType::JSON)
+///     .send()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentType(pub Mime);
+
+impl ContentType {
+    /// `application/json`
+    pub const JSON: HeaderValue = HeaderValue::from_static("application/json");
+    /// `application/x-www-form-urlencoded`
+    pub const FORM_URLENCODED: HeaderValue =
+        HeaderValue::from_static("application/x-www-form-urlencoded");
+    /// `text/plain; charset=utf-8`
+    pub const TEXT_PLAIN: HeaderValue = HeaderValue::from_static("text/plain; charset=utf-8");
+    /// `application/octet-stream`
+    pub const OCTET_STREAM: HeaderValue = HeaderValue::from_static("application/octet-stream");
+}
+
+impl TypedHeader for ContentType {
+    fn name() -> HeaderName {
+        CONTENT_TYPE
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        value.to_str().ok()?.parse().ok().map(ContentType)
+    }
+}
+
+/// The `Retry-After` header, as the time left to wait.
+///
+/// Both the delay-seconds and the HTTP-date forms are supported. A date in
+/// the past yields a zero duration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryAfter(pub Duration);
+
+impl TypedHeader for RetryAfter {
+    fn name() -> HeaderName {
+        RETRY_AFTER
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        parse_retry_after(value.to_str().ok()?, SystemTime::now()).map(RetryAfter)
+    }
+}
+
+/// The `Authorization` header.
+///
+/// Its value is marked as sensitive. It converts into a header name and value
+/// pair, so it can be set on a request:
+///
+/// ```rust
+/// use nightfly::header::Authorization;
+///
+/// # fn run() -> Result<(), nightfly::Error> {
+/// let (name, value) = Authorization::bearer("my-token")?.into();
+/// let res = nightfly::Client::new()
+///     .get("http://httpbin.org/bearer")
+///     .header(name, value)
+///     .send()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Authorization(HeaderValue);
+
+impl Authorization {
+    /// Basic authentication with a username and password.
+    pub fn basic(username: &str, password: &str) -> Authorization {
+        let credentials = format!("{}:{}", username, password);
+        let value = format!("Basic {}", base64_encode(credentials.as_bytes()));
+        // Base64 only produces valid header characters.
+        Authorization::sensitive(HeaderValue::from_str(&value).expect("valid header value"))
+    }
+
+    /// Bearer authentication with a token.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the token contains characters that aren't allowed in a
+    /// header value, such as newlines.
+    pub fn bearer(token: &str) -> crate::Result<Authorization> {
+        HeaderValue::from_str(&format!("Bearer {}", token))
+            .map(Authorization::sensitive)
+            .map_err(crate::error::builder)
+    }
+
+    /// The value of the header.
+    pub fn value(&self) -> &HeaderValue {
+        &self.0
+    }
+
+    fn sensitive(mut value: HeaderValue) -> Authorization {
+        value.set_sensitive(true);
+        Authorization(value)
+    }
+}
+
+impl From<Authorization> for (HeaderName, HeaderValue) {
+    fn from(auth: Authorization) -> (HeaderName, HeaderValue) {
+        (AUTHORIZATION, auth.0)
+    }
+}
+
+impl TypedHeader for Authorization {
+    fn name() -> HeaderName {
+        AUTHORIZATION
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        Some(Authorization(value.clone()))
+    }
+}
+
+pub(crate) fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_get() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("not a number"));
+
+        assert_eq!(
+            headers.typed_get::<ContentType>(),
+            Some(ContentType(mime::TEXT_HTML))
+        );
+        assert_eq!(
+            headers.typed_get::<RetryAfter>(),
+            Some(RetryAfter(Duration::from_secs(120)))
+        );
+        assert_eq!(headers.typed_get::<ContentLength>(), None);
+    }
+
+    #[test]
+    fn authorization() {
+        let auth = Authorization::basic("Aladdin", "open sesame");
+        assert_eq!(auth.value(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+        assert!(auth.value().is_sensitive());
+
+        let (name, value) = Authorization::bearer("token").unwrap().into();
+        assert_eq!(name, AUTHORIZATION);
+        assert_eq!(value, "Bearer token");
+        assert!(value.is_sensitive());
+
+        assert!(Authorization::bearer("bad\ntoken")
+            .unwrap_err()
+            .is_builder());
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use url::Url;
 
 /// A trait to try to convert some type into a `Url`.
@@ -10,6 +12,7 @@ impl IntoUrl for Url {}
 impl IntoUrl for String {}
 impl<'a> IntoUrl for &'a str {}
 impl<'a> IntoUrl for &'a String {}
+impl<'a> IntoUrl for Cow<'a, str> {}
 
 pub trait IntoUrlSealed {
     // Besides parsing as a valid `Url`, the `Url` must be a valid
@@ -63,6 +66,16 @@ impl<'a> IntoUrlSealed for String {
     }
 }
 
+impl<'a> IntoUrlSealed for Cow<'a, str> {
+    fn into_url(self) -> crate::Result<Url> {
+        (&*self).into_url()
+    }
+
+    fn as_str(&self) -> &str {
+        self.as_ref()
+    }
+}
+
 pub(crate) fn expect_uri(url: &Url) -> http::Uri {
     url.as_str()
         .parse()
@@ -85,4 +98,17 @@ mod tests {
             "builder error for url (file:///etc/hosts): URL scheme is not allowed"
         );
     }
+
+    #[test]
+    fn into_url_owned_and_cow() {
+        let url = "http://example.com/path";
+        let expected = Url::parse(url).unwrap();
+
+        assert_eq!(url.to_string().into_url().unwrap(), expected);
+        assert_eq!(Cow::Borrowed(url).into_url().unwrap(), expected);
+        assert_eq!(
+            Cow::<str>::Owned(url.to_owned()).into_url().unwrap(),
+            expected
+        );
+    }
 }
@@ -140,7 +140,8 @@
 //!
 //! These can be overwritten by adding a [`Proxy`](Proxy) to `ClientBuilder`
 //! i.e. `let proxy = nightfly::Proxy::http("https://secure.example")?;`
-//! or disabled by calling `ClientBuilder::no_proxy()`.
+//! or disabled by calling `ClientBuilder::no_proxy()` or
+//! `ClientBuilder::proxy_from_env(false)`.
 //!
 //! `socks` feature is required if you have configured socks proxy like this:
 //!
@@ -167,11 +168,21 @@
 //! enabled or disabled:
 //!
 //! - **cookies**: Provides cookie session support.
-//! - **gzip**: Provides response body gzip decompression.
-//! - **brotli**: Provides response body brotli decompression.
+//! - **gzip**: Provides response body gzip decompression and request body
+//!   gzip compression.
+//! - **brotli**: Provides response body brotli decompression and request body
+//!   brotli compression.
 //! - **deflate**: Provides response body deflate decompression.
+//! - **zstd**: Provides request body zstd compression.
 //! - **multipart**: Provides functionality for multipart forms.
 //! - **socks**: Provides SOCKS5 proxy support.
+//! - **msgpack_serializer**: Provides MessagePack request and response bodies.
+//! - **protobuf_serializer**: Provides Protocol Buffers request and response bodies.
+//! - **cbor_serializer**: Provides CBOR request and response bodies.
+//! - **xml**: Provides XML response body deserialization.
+//! - **csv**: Provides CSV response body parsing.
+//! - **tracing**: Emits `tracing` spans and events for requests.
+//! - **metrics**: Records request counters and durations through the `metrics` facade.
 //!
 //!
 //! [hyper]: http://hyper.rs
@@ -192,20 +203,24 @@ macro_rules! if_hyper {
     )*}
 }
 
-pub use http::header;
 pub use http::Method;
-pub use http::{StatusCode, Version};
+pub use http::StatusCode;
 pub use url::Url;
 
 // universal mods
 #[macro_use]
 mod error;
+pub mod body;
+pub mod header;
 mod into_url;
 mod response;
+mod status;
 
-pub use self::error::{Error, Result};
+pub use self::error::{Error, ErrorKind, Result};
 pub use self::into_url::IntoUrl;
 pub use self::response::ResponseBuilderExt;
+pub use self::status::StatusCodeExt;
+pub use self::version::Version;
 
 /// Shortcut method to quickly make a `GET` request.
 ///
@@ -238,6 +253,57 @@ pub fn get<T: IntoUrl>(url: T) -> crate::Result<HttpResponse> {
     Client::builder().build()?.get(url).send()
 }
 
+/// Shortcut method to `GET` a URL and read the response body as text.
+///
+/// This is [`get`] followed by [`HttpResponse::text`], and likewise creates a
+/// new internal `Client` on each call.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn run() -> Result<(), nightfly::Error> {
+/// let body = nightfly::get_text("https://www.rust-lang.org")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This function fails if [`get`] fails, or the body cannot be decoded.
+pub fn get_text<U: IntoUrl>(url: U) -> crate::Result<String> {
+    get(url)?.text()
+}
+
+/// Shortcut method to `GET` a URL and deserialize the response body as JSON.
+///
+/// This is [`get`] followed by [`HttpResponse::json`], and likewise creates a
+/// new internal `Client` on each call.
+///
+/// # Examples
+///
+/// ```rust
+/// # fn run() -> Result<(), nightfly::Error> {
+/// #[derive(serde::Deserialize)]
+/// struct Ip {
+///     origin: String,
+/// }
+///
+/// let ip: Ip = nightfly::get_json("http://httpbin.org/ip")?;
+/// println!("ip: {}", ip.origin);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This function fails if [`get`] fails, or the body is not valid JSON for
+/// `T`.
+// #[cfg(feature = "json")]
+// #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub fn get_json<T: serde::de::DeserializeOwned, U: IntoUrl>(url: U) -> crate::Result<T> {
+    get(url)?.json()
+}
+
 fn _assert_impls() {
     fn assert_send<T: Send>() {}
     fn assert_sync<T: Sync>() {}
@@ -266,10 +332,31 @@ fn _assert_impls() {
 // #[cfg(test)]
 // doctest!("../README.md");
 
+#[doc(hidden)]
+pub use self::lunatic_impl::fuzz_parse_response;
 #[cfg(feature = "multipart")]
 pub use self::lunatic_impl::multipart;
-pub use self::lunatic_impl::{Body, Client, ClientBuilder, HttpResponse, Request, RequestBuilder};
-pub use self::proxy::Proxy;
+#[cfg(any(
+    feature = "gzip",
+    feature = "deflate",
+    feature = "brotli",
+    feature = "zstd"
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(
+        feature = "gzip",
+        feature = "deflate",
+        feature = "brotli",
+        feature = "zstd"
+    )))
+)]
+pub use self::lunatic_impl::CompressionAlgorithm;
+pub use self::lunatic_impl::{
+    Body, Client, ClientBuilder, ClientFactory, HttpResponse, HttpStream, MailboxClient,
+    MailboxClientHandler, Request, RequestBuilder, SendRequest,
+};
+pub use self::proxy::{Proxy, ProxyScheme};
 #[cfg(feature = "__tls")]
 // Re-exports, to be removed in a future release
 pub use tls::{Certificate, Identity};
@@ -278,10 +365,20 @@ mod connect;
 #[cfg(feature = "cookies")]
 pub mod cookie;
 mod lunatic_impl;
+pub mod mock;
+pub mod pool;
 // #[cfg(feature = "trust-dns")]
 // mod dns;
 mod proxy;
 pub mod redirect;
 #[cfg(feature = "__tls")]
 pub mod tls;
-mod util;
+#[cfg(all(
+    feature = "__tls",
+    not(any(feature = "default-tls", feature = "__rustls"))
+))]
+compile_error!(
+    "TLS support needs a backend, enable the `native-tls` or a `rustls-tls(-...)` feature"
+);
+pub mod util;
+mod version;
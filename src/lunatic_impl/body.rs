@@ -3,70 +3,195 @@ use serde::{Deserialize, Serialize};
 
 /// Body struct
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
-pub struct Body(Vec<u8>);
+pub struct Body {
+    bytes: Vec<u8>,
+    // Read position of the `Read` implementation.
+    #[serde(skip)]
+    pos: usize,
+}
 
 impl Into<Body> for String {
     fn into(self) -> Body {
-        Body(self.into())
+        Body::from(self.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Body {
+        Body { bytes, pos: 0 }
     }
 }
 
 impl Into<Body> for Bytes {
     fn into(self) -> Body {
-        Body(self.into())
+        Body::from(Vec::from(self))
     }
 }
 
 impl Into<Bytes> for Body {
     fn into(self) -> Bytes {
-        Bytes::from(self.0)
+        Bytes::from(self.bytes)
     }
 }
 
 impl Body {
     /// empty body
     pub fn empty() -> Body {
-        Body(vec![])
+        Body::default()
     }
 
     /// length of body
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.bytes.len()
+    }
+
+    /// The number of bytes this body will send, if known up front.
+    ///
+    /// This is used to set the `Content-Length` header of a request. Bodies
+    /// backed by a buffer always know their length, so this currently always
+    /// returns `Some`.
+    pub fn len_hint(&self) -> Option<usize> {
+        Some(self.bytes.len())
     }
 
     /// retrieve body
     pub fn inner(self) -> Vec<u8> {
-        self.0
+        self.bytes
     }
 
     /// create a json body
     pub fn json<T: Serialize>(data: T) -> crate::Result<Body> {
-        match serde_json::to_string(&data) {
-            Ok(r) => Ok(Body(r.into())),
-            Err(e) => Err(crate::Error::new(
-                crate::error::Kind::Request,
-                Some("".to_string()),
-            )),
-        }
+        serde_json::to_vec(&data)
+            .map(Body::from)
+            .map_err(crate::error::builder)
     }
 
     /// create a regular text body
     pub fn text<T: Into<Vec<u8>>>(data: T) -> crate::Result<Body> {
-        Ok(Body(data.into()))
+        Ok(Body::from(data.into()))
+    }
+
+    /// create a MessagePack body
+    ///
+    /// Structs are encoded as maps with named fields.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `msgpack_serializer` feature to be enabled.
+    #[cfg(feature = "msgpack_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack_serializer")))]
+    pub fn msgpack<T: Serialize>(data: &T) -> crate::Result<Body> {
+        rmp_serde::to_vec_named(data)
+            .map(Body::from)
+            .map_err(crate::error::builder)
+    }
+
+    /// create a Protocol Buffers body
+    ///
+    /// No `Content-Type` header is set, callers should set it to the media
+    /// type expected by the server (e.g. `application/protobuf`).
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `protobuf_serializer` feature to be enabled.
+    #[cfg(feature = "protobuf_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf_serializer")))]
+    pub fn protobuf<M: protobuf::Message>(message: &M) -> crate::Result<Body> {
+        message
+            .write_to_bytes()
+            .map(Body::from)
+            .map_err(crate::error::builder)
+    }
+
+    /// create a CBOR body
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cbor_serializer` feature to be enabled.
+    #[cfg(feature = "cbor_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor_serializer")))]
+    pub fn cbor<T: Serialize>(data: &T) -> crate::Result<Body> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(data, &mut buf).map_err(crate::error::builder)?;
+        Ok(Body::from(buf))
     }
 }
 
+/// Reading from a `Body` advances through its bytes, starting at the first
+/// byte that hasn't been read yet.
 impl Read for Body {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        Cursor::new(self.0.clone()).read(buf)
+        let n = (&self.bytes[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
     }
 }
 
-use std::io::{Cursor, Read};
+/// A reader over the bytes of a [`Body`], implementing [`BufRead`].
+///
+/// This is useful to parse line based content, such as CSV or NDJSON, with
+/// [`BufRead::lines`]. The bytes are moved out of the body, not copied.
+///
+/// ```
+/// use std::io::BufRead;
+/// use nightfly::body::{Body, BodyReader};
+///
+/// let body = Body::text("first\nsecond\n").unwrap();
+/// let lines = BodyReader::from(body)
+///     .lines()
+///     .collect::<std::io::Result<Vec<_>>>()
+///     .unwrap();
+/// assert_eq!(lines, ["first", "second"]);
+/// ```
+#[derive(Debug)]
+pub struct BodyReader(Cursor<Vec<u8>>);
+
+/// The reader starts at the first byte of the body that hasn't been read yet.
+impl From<Body> for BodyReader {
+    fn from(body: Body) -> BodyReader {
+        let mut cursor = Cursor::new(body.bytes);
+        cursor.set_position(body.pos as u64);
+        BodyReader(cursor)
+    }
+}
+
+impl Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl BufRead for BodyReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt)
+    }
+}
+
+/// Writing to a `Body` appends the bytes to it.
+impl Write for Body {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.bytes.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.bytes.write_all(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+use std::io::{BufRead, Cursor, Read, Write};
 
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum EncodeError {
     #[cfg(feature = "msgpack_serializer")]
     #[cfg_attr(docsrs, doc(cfg(feature = "msgpack_serializer")))]
@@ -80,6 +205,10 @@ pub enum EncodeError {
     #[cfg_attr(docsrs, doc(cfg(feature = "protobuf_serializer")))]
     #[error("serialization to Protocol Buffers failed: {0}")]
     ProtocolBuffers(#[from] protobuf::Error),
+    #[cfg(feature = "cbor_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor_serializer")))]
+    #[error("serialization to CBOR failed: {0}")]
+    Cbor(#[from] ciborium::ser::Error<std::io::Error>),
     #[error("serialization failed: {0}")]
     IO(#[from] std::io::Error),
     #[error("serialization failed: {0}")]
@@ -87,6 +216,7 @@ pub enum EncodeError {
 }
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum DecodeError {
     // #[error("deserialization from Bincode failed: {0}")]
     // Bincode(#[from] bincode::Error),
@@ -102,6 +232,10 @@ pub enum DecodeError {
     #[cfg_attr(docsrs, doc(cfg(feature = "protobuf_serializer")))]
     #[error("deserialization from Protocol Buffers failed: {0}")]
     ProtocolBuffers(#[from] protobuf::Error),
+    #[cfg(feature = "cbor_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor_serializer")))]
+    #[error("deserialization from CBOR failed: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
     #[error("serialization failed: {0}")]
     IO(#[from] std::io::Error),
     #[error("deserialization failed: {0}")]
@@ -140,3 +274,147 @@ where
         Ok(serde_json::from_reader(reader)?)
     }
 }
+
+/// A `MessagePack` serializer.
+///
+/// It can serialize any message that satisfies the traits:
+/// - `serde::Serialize`
+/// - `serde::de::DeserializeOwned`
+///
+/// Structs are encoded as maps with named fields, so messages stay readable
+/// by peers that don't share the exact struct definition.
+#[cfg(feature = "msgpack_serializer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack_serializer")))]
+#[derive(Debug, Hash)]
+pub struct MessagePack {}
+
+#[cfg(feature = "msgpack_serializer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack_serializer")))]
+impl<M> Serializer<M> for MessagePack
+where
+    M: serde::Serialize + serde::de::DeserializeOwned,
+{
+    fn encode(message: &M) -> Result<Vec<u8>, EncodeError> {
+        Ok(rmp_serde::to_vec_named(message)?)
+    }
+
+    fn decode<R: Read>(reader: R) -> Result<M, DecodeError> {
+        Ok(rmp_serde::from_read(reader)?)
+    }
+}
+
+/// A `ProtocolBuffers` serializer.
+///
+/// It can serialize any message that implements `protobuf::Message`.
+#[cfg(feature = "protobuf_serializer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf_serializer")))]
+#[derive(Debug, Hash)]
+pub struct ProtocolBuffers {}
+
+#[cfg(feature = "protobuf_serializer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf_serializer")))]
+impl<M> Serializer<M> for ProtocolBuffers
+where
+    M: protobuf::Message,
+{
+    fn encode(message: &M) -> Result<Vec<u8>, EncodeError> {
+        Ok(message.write_to_bytes()?)
+    }
+
+    fn decode<R: Read>(mut reader: R) -> Result<M, DecodeError> {
+        Ok(M::parse_from_reader(&mut reader)?)
+    }
+}
+
+/// A `Cbor` serializer.
+///
+/// It can serialize any message that satisfies the traits:
+/// - `serde::Serialize`
+/// - `serde::de::DeserializeOwned`
+#[cfg(feature = "cbor_serializer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor_serializer")))]
+#[derive(Debug, Hash)]
+pub struct Cbor {}
+
+#[cfg(feature = "cbor_serializer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor_serializer")))]
+impl<M> Serializer<M> for Cbor
+where
+    M: serde::Serialize + serde::de::DeserializeOwned,
+{
+    fn encode(message: &M) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(message, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn decode<R: Read>(reader: R) -> Result<M, DecodeError> {
+        Ok(ciborium::de::from_reader(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Body;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn write_appends_to_body() {
+        let mut body = Body::text("hello").unwrap();
+        body.write_all(b", world").unwrap();
+
+        assert_eq!(body.inner(), b"hello, world");
+    }
+
+    #[test]
+    fn read_in_chunks() {
+        let mut body = Body::text("hello, world").unwrap();
+        let mut buf = [0; 5];
+
+        assert_eq!(body.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+        assert_eq!(body.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b", wor");
+        assert_eq!(body.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ld");
+        assert_eq!(body.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn body_reader_starts_at_unread_bytes() {
+        use super::BodyReader;
+        use std::io::BufRead;
+
+        let mut body = Body::text("skip\nfirst\nsecond").unwrap();
+        let mut buf = [0; 5];
+        body.read_exact(&mut buf).unwrap();
+
+        let lines = BodyReader::from(body)
+            .lines()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(lines, ["first", "second"]);
+    }
+
+    #[test]
+    fn json_error_keeps_source() {
+        use std::collections::BTreeMap;
+        use std::error::Error;
+
+        // JSON object keys must be strings.
+        let data = BTreeMap::from([((1, 2), "value")]);
+        let err = Body::json(data).unwrap_err();
+
+        assert!(err.is_builder());
+        assert!(err.source().unwrap().is::<serde_json::Error>());
+    }
+
+    #[test]
+    fn serialize_into_body() {
+        let mut body = Body::empty();
+        serde_json::to_writer(&mut body, &serde_json::json!({ "lang": "rust" })).unwrap();
+
+        assert_eq!(body.len(), 15);
+        assert_eq!(body.inner(), br#"{"lang":"rust"}"#);
+    }
+}
@@ -0,0 +1,120 @@
+use std::io;
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
+use std::io::Write;
+
+use http::HeaderValue;
+
+/// An algorithm used to compress a request body.
+///
+/// Each variant requires the optional feature of the same name to be enabled,
+/// and the type is only exported when at least one of them is.
+///
+/// See [`RequestBuilder::compress_body`](crate::RequestBuilder::compress_body).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompressionAlgorithm {
+    /// Compress with gzip, sent as `Content-Encoding: gzip`.
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    Gzip,
+    /// Compress with zlib deflate, sent as `Content-Encoding: deflate`.
+    #[cfg(feature = "deflate")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "deflate")))]
+    Deflate,
+    /// Compress with brotli, sent as `Content-Encoding: br`.
+    #[cfg(feature = "brotli")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "brotli")))]
+    Brotli,
+    /// Compress with zstd, sent as `Content-Encoding: zstd`.
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    /// The `Content-Encoding` header value for this algorithm.
+    pub(crate) fn content_encoding(&self) -> HeaderValue {
+        match *self {
+            #[cfg(feature = "gzip")]
+            CompressionAlgorithm::Gzip => HeaderValue::from_static("gzip"),
+            #[cfg(feature = "deflate")]
+            CompressionAlgorithm::Deflate => HeaderValue::from_static("deflate"),
+            #[cfg(feature = "brotli")]
+            CompressionAlgorithm::Brotli => HeaderValue::from_static("br"),
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd => HeaderValue::from_static("zstd"),
+        }
+    }
+
+    /// Compress `data` with the default level of this algorithm.
+    #[cfg_attr(
+        not(any(
+            feature = "gzip",
+            feature = "deflate",
+            feature = "brotli",
+            feature = "zstd"
+        )),
+        allow(unused_variables)
+    )]
+    pub(crate) fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match *self {
+            #[cfg(feature = "gzip")]
+            CompressionAlgorithm::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "deflate")]
+            CompressionAlgorithm::Deflate => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "brotli")]
+            CompressionAlgorithm::Brotli => {
+                // Quality 11 and a 4 MiB window are the brotli defaults.
+                let mut encoder = brotli_crate::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+                encoder.write_all(data)?;
+                Ok(encoder.into_inner())
+            }
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd => zstd_crate::stream::encode_all(data, 0),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "deflate", feature = "brotli")))]
+mod tests {
+    use super::CompressionAlgorithm;
+    use std::io::Read;
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn deflate_roundtrip() {
+        let data = b"hello hello hello hello hello hello";
+        let compressed = CompressionAlgorithm::Deflate.compress(data).unwrap();
+        assert_eq!(CompressionAlgorithm::Deflate.content_encoding(), "deflate");
+
+        let mut decoded = Vec::new();
+        flate2::read::ZlibDecoder::new(&compressed[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(&decoded[..], &data[..]);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn brotli_roundtrip() {
+        let data = b"hello hello hello hello hello hello";
+        let compressed = CompressionAlgorithm::Brotli.compress(data).unwrap();
+        assert_eq!(CompressionAlgorithm::Brotli.content_encoding(), "br");
+
+        let mut decoded = Vec::new();
+        brotli_crate::Decompressor::new(&compressed[..], 4096)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(&decoded[..], &data[..]);
+    }
+}
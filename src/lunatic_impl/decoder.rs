@@ -1,31 +1,32 @@
 use std::convert::TryFrom;
 use std::fmt;
-use std::io::{Cursor, Read};
+use std::io::{self, Cursor, Read};
 
 #[cfg(feature = "gzip")]
-use async_compression::tokio::bufread::GzipDecoder;
-
-#[cfg(feature = "brotli")]
-use async_compression::tokio::bufread::BrotliDecoder;
+use flate2::read::GzDecoder;
 
 #[cfg(feature = "deflate")]
-use async_compression::tokio::bufread::ZlibDecoder;
+use flate2::read::ZlibDecoder;
+
+#[cfg(feature = "brotli")]
+use brotli_crate::Decompressor;
 
-use bytes::Bytes;
-use http::HeaderMap;
+use http::header::{CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING};
+use http::{HeaderMap, Method};
 
 use httparse::{Status, EMPTY_HEADER};
 use lunatic::net::TcpStream;
-#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate"))]
-use tokio_util::codec::{BytesCodec, FramedRead};
-#[cfg(any(feature = "gzip", feature = "brotli", feature = "deflate"))]
-use tokio_util::io::StreamReader;
 use url::Url;
 
-use super::super::Body;
 use super::http_stream::HttpStream;
-use crate::{error, HttpResponse};
+use crate::{error, HttpResponse, StatusCodeExt, Version};
 
+/// The response encodings a client accepts, sent as its `Accept-Encoding`.
+///
+/// `Accepts::default()` accepts every encoding whose feature (`gzip`,
+/// `brotli` or `deflate`) was enabled at compile time, and nothing else. It
+/// is implemented by hand rather than derived, since a derived impl would
+/// accept nothing.
 #[derive(Clone, Copy, Debug)]
 pub(super) struct Accepts {
     #[cfg(feature = "gzip")]
@@ -36,41 +37,31 @@ pub(super) struct Accepts {
     pub(super) deflate: bool,
 }
 
-/// A response decompressor over a non-blocking stream of chunks.
+/// A response body decompressor.
 ///
-/// The inner decoder may be constructed asynchronously.
+/// The whole body is already read when the decoder is created, so it
+/// decompresses from memory.
 pub(crate) struct Decoder {
     inner: Inner,
 }
 
 enum Inner {
     /// A `PlainText` decoder just returns the response content as is.
-    PlainText(Vec<u8>),
+    ///
+    /// The cursor keeps the read position across calls to `read`.
+    PlainText(Cursor<Vec<u8>>),
 
     /// A `Gzip` decoder will uncompress the gzipped response content before returning it.
     #[cfg(feature = "gzip")]
-    Gzip(FramedRead<GzipDecoder<StreamReader<Peekable<IoStream>, Bytes>>, BytesCodec>),
-
-    /// A `Brotli` decoder will uncompress the brotlied response content before returning it.
-    #[cfg(feature = "brotli")]
-    Brotli(FramedRead<BrotliDecoder<StreamReader<Peekable<IoStream>, Bytes>>, BytesCodec>),
+    Gzip(GzDecoder<Cursor<Vec<u8>>>),
 
     /// A `Deflate` decoder will uncompress the deflated response content before returning it.
     #[cfg(feature = "deflate")]
-    Deflate(FramedRead<ZlibDecoder<StreamReader<Peekable<IoStream>, Bytes>>, BytesCodec>),
-
-    /// A decoder that doesn't have a value yet.
-    #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
-    Pending(Pending),
-}
+    Deflate(ZlibDecoder<Cursor<Vec<u8>>>),
 
-enum DecoderType {
-    #[cfg(feature = "gzip")]
-    Gzip,
+    /// A `Brotli` decoder will uncompress the brotlied response content before returning it.
     #[cfg(feature = "brotli")]
-    Brotli,
-    #[cfg(feature = "deflate")]
-    Deflate,
+    Brotli(Decompressor<Cursor<Vec<u8>>>),
 }
 
 impl fmt::Debug for Decoder {
@@ -83,7 +74,7 @@ impl Decoder {
     #[cfg(feature = "blocking")]
     pub(crate) fn empty() -> Decoder {
         Decoder {
-            inner: Inner::PlainText(Body::empty().into_stream()),
+            inner: Inner::PlainText(Cursor::new(Vec::new())),
         }
     }
 
@@ -92,64 +83,50 @@ impl Decoder {
     /// This decoder will emit the underlying chunks as-is.
     fn plain_text(body: Vec<u8>) -> Decoder {
         Decoder {
-            inner: Inner::PlainText(body),
+            inner: Inner::PlainText(Cursor::new(body)),
         }
     }
 
     /// A gzip decoder.
     ///
-    /// This decoder will buffer and decompress chunks that are gzipped.
+    /// This decoder will decompress a gzipped body.
     #[cfg(feature = "gzip")]
-    fn gzip(body: Body) -> Decoder {
-        use futures_util::StreamExt;
-
+    fn gzip(body: Vec<u8>) -> Decoder {
         Decoder {
-            inner: Inner::Pending(Pending(
-                IoStream(body.into_stream()).peekable(),
-                DecoderType::Gzip,
-            )),
+            inner: Inner::Gzip(GzDecoder::new(Cursor::new(body))),
         }
     }
 
-    /// A brotli decoder.
+    /// A deflate decoder.
     ///
-    /// This decoder will buffer and decompress chunks that are brotlied.
-    #[cfg(feature = "brotli")]
-    fn brotli(body: Body) -> Decoder {
-        use futures_util::StreamExt;
-
+    /// This decoder will decompress a deflated body.
+    #[cfg(feature = "deflate")]
+    fn deflate(body: Vec<u8>) -> Decoder {
         Decoder {
-            inner: Inner::Pending(Pending(
-                IoStream(body.into_stream()).peekable(),
-                DecoderType::Brotli,
-            )),
+            inner: Inner::Deflate(ZlibDecoder::new(Cursor::new(body))),
         }
     }
 
-    /// A deflate decoder.
+    /// A brotli decoder.
     ///
-    /// This decoder will buffer and decompress chunks that are deflated.
-    #[cfg(feature = "deflate")]
-    fn deflate(body: Body) -> Decoder {
-        use futures_util::StreamExt;
-
+    /// This decoder will decompress a brotlied body.
+    #[cfg(feature = "brotli")]
+    fn brotli(body: Vec<u8>) -> Decoder {
         Decoder {
-            inner: Inner::Pending(Pending(
-                IoStream(body.into_stream()).peekable(),
-                DecoderType::Deflate,
-            )),
+            inner: Inner::Brotli(Decompressor::new(Cursor::new(body), 4096)),
         }
     }
 
-    pub fn decode(&self) -> Vec<u8> {
-        match &self.inner {
-            Inner::PlainText(text) => text.clone(),
-        }
+    /// Decode the whole body.
+    pub fn decode(mut self) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        self.read_to_end(&mut body)?;
+        Ok(body)
     }
 
-    #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
+    #[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
     fn detect_encoding(headers: &mut HeaderMap, encoding_str: &str) -> bool {
-        use http::header::{CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING};
+        use http::header::CONTENT_ENCODING;
         use lunatic_log::warn;
 
         let mut is_content_encoded = {
@@ -177,13 +154,15 @@ impl Decoder {
         is_content_encoded
     }
 
-    /// Constructs a Decoder from a hyper request.
-    ///
-    /// A decoder is just a wrapper around the hyper request that knows
-    /// how to decode the content body of the request.
+    /// Constructs a Decoder for a response body.
     ///
-    /// Uses the correct variant by inspecting the Content-Encoding header.
+    /// Uses the correct variant by inspecting the Content-Encoding header. An
+    /// empty body, e.g. the response to a `HEAD` request, is kept as is.
     pub(super) fn detect(_headers: &mut HeaderMap, body: Vec<u8>, _accepts: Accepts) -> Decoder {
+        if body.is_empty() {
+            return Decoder::plain_text(body);
+        }
+
         #[cfg(feature = "gzip")]
         {
             if _accepts.gzip && Decoder::detect_encoding(_headers, "gzip") {
@@ -210,45 +189,15 @@ impl Decoder {
 }
 
 impl Read for Decoder {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        // Do a read or poll for a pending decoder value.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match self.inner {
-            #[cfg(any(feature = "brotli", feature = "gzip", feature = "deflate"))]
-            Inner::Pending(ref mut future) => match Pin::new(future).poll(cx) {
-                Poll::Ready(Ok(inner)) => {
-                    self.inner = inner;
-                    return self.poll_next(cx);
-                }
-                Poll::Ready(Err(e)) => {
-                    return Poll::Ready(Some(Err(crate::error::decode_io(e))));
-                }
-                Poll::Pending => return Poll::Pending,
-            },
-            Inner::PlainText(ref mut body) => Cursor::new(body).read(buf),
+            Inner::PlainText(ref mut body) => body.read(buf),
             #[cfg(feature = "gzip")]
-            Inner::Gzip(ref mut decoder) => {
-                return match futures_core::ready!(Pin::new(decoder).poll_next(cx)) {
-                    Some(Ok(bytes)) => Poll::Ready(Some(Ok(bytes.freeze()))),
-                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::decode_io(err)))),
-                    None => Poll::Ready(None),
-                };
-            }
-            #[cfg(feature = "brotli")]
-            Inner::Brotli(ref mut decoder) => {
-                return match futures_core::ready!(Pin::new(decoder).poll_next(cx)) {
-                    Some(Ok(bytes)) => Poll::Ready(Some(Ok(bytes.freeze()))),
-                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::decode_io(err)))),
-                    None => Poll::Ready(None),
-                };
-            }
+            Inner::Gzip(ref mut decoder) => decoder.read(buf),
             #[cfg(feature = "deflate")]
-            Inner::Deflate(ref mut decoder) => {
-                return match futures_core::ready!(Pin::new(decoder).poll_next(cx)) {
-                    Some(Ok(bytes)) => Poll::Ready(Some(Ok(bytes.freeze()))),
-                    Some(Err(err)) => Poll::Ready(Some(Err(crate::error::decode_io(err)))),
-                    None => Poll::Ready(None),
-                };
-            }
+            Inner::Deflate(ref mut decoder) => decoder.read(buf),
+            #[cfg(feature = "brotli")]
+            Inner::Brotli(ref mut decoder) => decoder.read(buf),
         }
     }
 }
@@ -331,23 +280,128 @@ const MAX_REQUEST_SIZE: usize = 10 * 1024 * 1024;
 const REQUEST_BUFFER_SIZE: usize = 4096;
 const MAX_HEADERS: usize = 128;
 
-/// The result of parsing a response from a buffer.
-type ResponseResult = Result<HttpResponse, ParseResponseError>;
+/// The result of parsing a response from a stream.
+///
+/// The stream is returned with the response if the connection can be reused
+/// for another request, and is `None` if the server closes it.
+pub(crate) type ResponseResult = Result<(HttpResponse, Option<HttpStream>), ParseResponseError>;
 
+/// The ways parsing a response can fail.
+///
+/// Parsing is likely to learn about more failures, so callers should
+/// include a wildcard arm when matching on it.
 #[derive(Debug)]
+#[non_exhaustive]
 pub(crate) enum ParseResponseError {
     TcpStreamClosed,
     TcpStreamClosedWithoutData,
     HttpParseError(httparse::Error),
     ResponseTooLarge,
     UnknownCode,
+    InvalidChunk,
+    InvalidContentLength,
+    /// Reading from or writing to the stream took longer than its timeout.
+    TimedOut,
+}
+
+impl ParseResponseError {
+    /// The error for a failed read or write on the stream, which is
+    /// `otherwise` unless it timed out.
+    pub(crate) fn from_io(err: &io::Error, otherwise: ParseResponseError) -> ParseResponseError {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => ParseResponseError::TimedOut,
+            _ => otherwise,
+        }
+    }
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            http.response.status_code = tracing::field::Empty,
+            http.response.body.size = tracing::field::Empty,
+        )
+    )
+)]
 pub(crate) fn parse_response(
-    mut response_buffer: Vec<u8>,
+    response_buffer: Vec<u8>,
     mut stream: HttpStream,
     url: Url,
+    method: &Method,
 ) -> ResponseResult {
+    let (res, rest) = parse_pipelined_response(response_buffer, &mut stream, url, method)?;
+    let stream = if can_reuse(&res, &rest, method) {
+        Some(stream)
+    } else {
+        None
+    };
+    Ok((res, stream))
+}
+
+/// Whether the connection a response was read from can send another request.
+///
+/// Besides staying open, the end of the response must be known from its
+/// head, and nothing may have been read past it, since those bytes don't
+/// belong to any request sent on the connection.
+fn can_reuse(res: &HttpResponse, rest: &[u8], method: &Method) -> bool {
+    let has_length = *method == Method::HEAD
+        || !res.status.allows_body()
+        || res.headers.contains_key(CONTENT_LENGTH)
+        || res
+            .headers
+            .get_all(TRANSFER_ENCODING)
+            .iter()
+            .any(|value| is_chunked(value.as_bytes()));
+    rest.is_empty() && has_length && keeps_alive(res)
+}
+
+/// Whether a `Transfer-Encoding` value contains the `chunked` coding.
+fn is_chunked(value: &[u8]) -> bool {
+    std::str::from_utf8(value).map_or(false, |value| {
+        value
+            .split(',')
+            .any(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+    })
+}
+
+/// Whether the connection a response was read from stays open afterwards.
+///
+/// HTTP/1.1 connections are persistent unless the server sends
+/// `Connection: close`, older versions only if it sends
+/// `Connection: keep-alive`.
+fn keeps_alive(res: &HttpResponse) -> bool {
+    let mut options = res
+        .headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim);
+    if res.version < Version::HTTP_11 {
+        options.any(|option| option.eq_ignore_ascii_case("keep-alive"))
+    } else {
+        !options.any(|option| option.eq_ignore_ascii_case("close"))
+    }
+}
+
+/// Parse one response from `response_buffer`, reading more from `stream` as
+/// needed.
+///
+/// Besides the response, this returns the bytes that were read beyond its end,
+/// i.e. the start of the next pipelined response on the same connection. They
+/// should be passed as the `response_buffer` of the next call.
+///
+/// `method` is the method of the request the response answers, since the
+/// response to a `HEAD` request never has a body.
+pub(crate) fn parse_pipelined_response<R: Read>(
+    mut response_buffer: Vec<u8>,
+    mut stream: R,
+    url: Url,
+    method: &Method,
+) -> Result<(HttpResponse, Vec<u8>), ParseResponseError> {
     let mut buffer = [0_u8; REQUEST_BUFFER_SIZE];
     let mut headers = [EMPTY_HEADER; MAX_HEADERS];
 
@@ -365,15 +419,16 @@ pub(crate) fn parse_response(
                 }
                 Status::Partial => {
                     // Read more data from TCP stream
-                    let n = stream.read(&mut buffer);
-                    if n.is_err() || *n.as_ref().unwrap() == 0 {
-                        if response_buffer.is_empty() {
-                            return Err(ParseResponseError::TcpStreamClosedWithoutData);
-                        } else {
-                            return Err(ParseResponseError::TcpStreamClosed);
-                        }
-                    }
-                    let n = n.unwrap();
+                    let closed = if response_buffer.is_empty() {
+                        ParseResponseError::TcpStreamClosedWithoutData
+                    } else {
+                        ParseResponseError::TcpStreamClosed
+                    };
+                    let n = match stream.read(&mut buffer) {
+                        Ok(0) => return Err(closed),
+                        Ok(n) => n,
+                        Err(e) => return Err(ParseResponseError::from_io(&e, closed)),
+                    };
                     // Invalidate references in `headers` that could point to the previous
                     // `response_buffer` before extending it.
                     headers = [EMPTY_HEADER; MAX_HEADERS];
@@ -404,63 +459,190 @@ pub(crate) fn parse_response(
             return Err(ParseResponseError::UnknownCode);
         }
     };
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("http.response.status_code", status_code.as_u16());
     let response = http::Response::builder().status(status_code);
-    let mut content_lengt = None;
+    let content_lengt = match response_raw
+        .headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+    {
+        Some(header) => Some(
+            std::str::from_utf8(header.value)
+                .ok()
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .ok_or(ParseResponseError::InvalidContentLength)?,
+        ),
+        None => None,
+    };
+    let mut chunked = false;
     let response = response_raw
         .headers
         .iter()
         .fold(response, |response, header| {
-            if header.name.to_lowercase() == "content-length" {
-                let value_string = std::str::from_utf8(header.value).unwrap();
-                let length = value_string.parse::<usize>().unwrap();
-                content_lengt = Some(length);
+            if header.name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = is_chunked(header.value);
             }
             response.header(header.name, header.value)
         });
+    #[cfg(feature = "tracing")]
+    if let Some(content_length) = content_lengt {
+        tracing::Span::current().record("http.response.body.size", content_length);
+    }
     // If content-length exists, response has a body
     let res = response.body(vec![0u8; 0]).unwrap();
     let mut res = HttpResponse {
         headers: res.headers().to_owned(),
         status: res.status().to_owned(),
-        version: res.version().to_owned(),
+        version: res.version().into(),
         body: vec![],
         url,
+        // Only known to the caller, which has the stream.
+        remote_addr: None,
     };
-    if let Some(content_lengt) = content_lengt {
-        #[allow(clippy::comparison_chain)]
-        if response_buffer[offset..].len() == content_lengt {
-            // Complete content is captured from the response w/o trailing pipelined
-            // responses.
-            res.body = response_buffer[offset..].to_owned();
-            return Ok(res);
-
-        // } else if response_buffer[offset..].len() > content_lengt {
-        //     // Complete content is captured from the response with trailing pipelined
-        //     // responses.
-        //         response
-        //             .body(Body::from_slice(&response_buffer[offset..]))
-        //             .unwrap(),
-        //         Vec::from(&response_buffer[offset + content_lengt..])
+    if *method == Method::HEAD || !status_code.allows_body() {
+        // Anything after the head belongs to a trailing pipelined response,
+        // even if a `Content-Length` was sent.
+        Ok((res, response_buffer.split_off(offset)))
+    } else if chunked {
+        // `Transfer-Encoding` overrides `Content-Length` (RFC 7230, section
+        // 3.3.3), otherwise the two could disagree on where the next
+        // response starts.
+        let (body, end) = read_chunked_body(&mut response_buffer, offset, &mut stream)?;
+        res.body = body;
+        Ok((res, response_buffer.split_off(end)))
+    } else if let Some(content_lengt) = content_lengt {
+        // The length is untrusted, so refuse a body that can't fit instead of
+        // reading it.
+        if offset.saturating_add(content_lengt) > MAX_REQUEST_SIZE {
+            return Err(ParseResponseError::ResponseTooLarge);
+        }
+        let available = response_buffer[offset..].len();
+        if available >= content_lengt {
+            // Complete content is captured from the response, possibly with trailing
+            // pipelined responses.
+            let end = offset + content_lengt;
+            res.body = response_buffer[offset..end].to_owned();
+            Ok((res, response_buffer.split_off(end)))
         } else {
-            // Read the rest from TCP stream to form a full response
-            let rest = content_lengt - response_buffer[offset..].len();
-            let mut buffer = vec![0u8; rest];
-            stream.read_exact(&mut buffer).unwrap();
-            response_buffer.extend(&buffer);
+            // Read the rest from TCP stream to form a full response, only
+            // growing the buffer as data actually arrives.
+            let missing = (content_lengt - available) as u64;
+            let read = (&mut stream)
+                .take(missing)
+                .read_to_end(&mut response_buffer)
+                .map_err(|e| {
+                    ParseResponseError::from_io(&e, ParseResponseError::TcpStreamClosed)
+                })?;
+            if (read as u64) < missing {
+                return Err(ParseResponseError::TcpStreamClosed);
+            }
             res.body = response_buffer[offset..].to_owned();
-            return Ok(res);
+            Ok((res, Vec::new()))
         }
     } else {
-        // If the offset points to the end of `responses_buffer` we have a full response,
-        // w/o a trailing pipelined response.
-        if response_buffer[offset..].is_empty() {
-            return Ok(res);
-        } else {
-            // PipelinedResponses::from_pipeline(
-            return Ok(res);
-            //     Vec::from(&response_buffer[offset..]),
-            // )
+        // Without a length the body ends when the server closes the
+        // connection.
+        let limit = (MAX_REQUEST_SIZE + 1).saturating_sub(response_buffer.len());
+        (&mut stream)
+            .take(limit as u64)
+            .read_to_end(&mut response_buffer)
+            .map_err(|e| ParseResponseError::from_io(&e, ParseResponseError::TcpStreamClosed))?;
+        if response_buffer.len() > MAX_REQUEST_SIZE {
+            return Err(ParseResponseError::ResponseTooLarge);
+        }
+        res.body = response_buffer.split_off(offset);
+        Ok((res, Vec::new()))
+    }
+}
+
+/// Parse a response whose first `buffered` bytes were already read, reading
+/// the rest from `stream`, and return whether it parsed.
+///
+/// Only public for the fuzzing harness in `tests/fuzz_parse_response.rs`.
+#[doc(hidden)]
+pub fn fuzz_parse_response(buffered: &[u8], stream: &[u8], method: &Method) -> bool {
+    let url = Url::parse("http://example.domain/").expect("valid url");
+    parse_pipelined_response(buffered.to_vec(), stream, url, method).is_ok()
+}
+
+/// Decode a chunked body starting at `chunk_offset` of `response_buffer`,
+/// reading more from `stream` as needed.
+///
+/// Returns the decoded body and the offset just past the last chunk and its
+/// trailer section.
+fn read_chunked_body<R: Read>(
+    response_buffer: &mut Vec<u8>,
+    mut chunk_offset: usize,
+    stream: &mut R,
+) -> Result<(Vec<u8>, usize), ParseResponseError> {
+    let mut body = Vec::new();
+    loop {
+        // `parse_chunk_size` skips any chunk extensions (`<size>;ext=val`), the
+        // returned index points past the CRLF ending the chunk-size line.
+        match httparse::parse_chunk_size(&response_buffer[chunk_offset..]) {
+            Ok(Status::Complete((idx, 0))) => {
+                // Last chunk, skip the trailer fields up to the empty line.
+                let mut line_start = chunk_offset + idx;
+                loop {
+                    let line_end = match find_crlf(&response_buffer[line_start..]) {
+                        Some(pos) => line_start + pos,
+                        None => {
+                            read_more(response_buffer, stream)?;
+                            continue;
+                        }
+                    };
+                    if line_end == line_start {
+                        return Ok((body, line_end + 2));
+                    }
+                    line_start = line_end + 2;
+                }
+            }
+            Ok(Status::Complete((idx, size))) => {
+                let data_start = chunk_offset + idx;
+                let data_end = usize::try_from(size)
+                    .ok()
+                    .and_then(|size| data_start.checked_add(size))
+                    .filter(|data_end| data_end.checked_add(2).is_some())
+                    .ok_or(ParseResponseError::ResponseTooLarge)?;
+                while response_buffer.len() < data_end + 2 {
+                    read_more(response_buffer, stream)?;
+                }
+                // The chunk data must be followed by a CRLF.
+                if &response_buffer[data_end..data_end + 2] != b"\r\n" {
+                    return Err(ParseResponseError::InvalidChunk);
+                }
+                body.extend_from_slice(&response_buffer[data_start..data_end]);
+                chunk_offset = data_end + 2;
+            }
+            Ok(Status::Partial) => read_more(response_buffer, stream)?,
+            Err(_) => return Err(ParseResponseError::InvalidChunk),
+        }
+    }
+}
+
+fn find_crlf(buf: &[u8]) -> Option<usize> {
+    buf.windows(2).position(|window| window == b"\r\n")
+}
+
+fn read_more<R: Read>(
+    response_buffer: &mut Vec<u8>,
+    stream: &mut R,
+) -> Result<(), ParseResponseError> {
+    let mut buffer = [0_u8; REQUEST_BUFFER_SIZE];
+    match stream.read(&mut buffer) {
+        Ok(n) if n > 0 => {
+            response_buffer.extend(&buffer[..n]);
+            if response_buffer.len() > MAX_REQUEST_SIZE {
+                return Err(ParseResponseError::ResponseTooLarge);
+            }
+            Ok(())
         }
+        Ok(_) => Err(ParseResponseError::TcpStreamClosed),
+        Err(e) => Err(ParseResponseError::from_io(
+            &e,
+            ParseResponseError::TcpStreamClosed,
+        )),
     }
 }
 
@@ -529,6 +711,7 @@ impl Accepts {
 }
 
 impl Default for Accepts {
+    /// Accepts every encoding enabled at compile time.
     fn default() -> Accepts {
         Accepts {
             #[cfg(feature = "gzip")]
@@ -540,3 +723,328 @@ impl Default for Accepts {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        can_reuse, keeps_alive, parse_pipelined_response, read_chunked_body, Accepts, Decoder,
+        ParseResponseError,
+    };
+    use http::Method;
+    use std::io::{self, Cursor, Read};
+    use url::Url;
+
+    #[test]
+    fn default_accepts_enabled_features() {
+        let accepts = Accepts::default();
+        assert_eq!(accepts.is_gzip(), cfg!(feature = "gzip"));
+        assert_eq!(accepts.is_brotli(), cfg!(feature = "brotli"));
+        assert_eq!(accepts.is_deflate(), cfg!(feature = "deflate"));
+
+        assert_eq!(Accepts::none().as_str(), None);
+    }
+
+    #[test]
+    fn plain_text_read_advances() {
+        let mut decoder = Decoder::plain_text(b"hello, world".to_vec());
+        let mut buf = [0; 7];
+
+        assert_eq!(decoder.read(&mut buf).unwrap(), 7);
+        assert_eq!(&buf, b"hello, ");
+        assert_eq!(decoder.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"world");
+        assert_eq!(decoder.read(&mut buf).unwrap(), 0);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_body_is_decoded() {
+        use http::header::{CONTENT_ENCODING, CONTENT_LENGTH};
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(b"hello, world").unwrap();
+        let body = encoder.finish().unwrap();
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+        headers.insert(CONTENT_LENGTH, body.len().into());
+        let decoder = Decoder::detect(&mut headers, body, Accepts::default());
+
+        assert_eq!(decoder.decode().unwrap(), b"hello, world");
+        assert!(headers.is_empty());
+
+        // The body of a response to `HEAD` is empty, even when it's encoded.
+        let mut headers = http::HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+        let decoder = Decoder::detect(&mut headers, Vec::new(), Accepts::default());
+        assert!(decoder.decode().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pipelined_responses() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello\
+            HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nworld\r\n0\r\n\r\n\
+            HTTP/1.1 204 No Content\r\n\r\n"
+            .to_vec();
+        let stream = || Cursor::new(Vec::new());
+
+        let (res, rest) =
+            parse_pipelined_response(buf, stream(), url.clone(), &Method::GET).unwrap();
+        assert_eq!(res.body, b"hello");
+
+        let (res, rest) =
+            parse_pipelined_response(rest, stream(), url.clone(), &Method::GET).unwrap();
+        assert_eq!(res.body, b"world");
+
+        let (res, rest) = parse_pipelined_response(rest, stream(), url, &Method::GET).unwrap();
+        assert_eq!(res.status, http::StatusCode::NO_CONTENT);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn keep_alive() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let keeps_alive = |raw: &[u8]| {
+            let (res, _) = parse_pipelined_response(
+                raw.to_vec(),
+                Cursor::new(vec![]),
+                url.clone(),
+                &Method::GET,
+            )
+            .unwrap();
+            keeps_alive(&res)
+        };
+
+        assert!(keeps_alive(b"HTTP/1.1 204 No Content\r\n\r\n"));
+        assert!(!keeps_alive(
+            b"HTTP/1.1 204 No Content\r\nConnection: Close\r\n\r\n"
+        ));
+        assert!(!keeps_alive(b"HTTP/1.0 204 No Content\r\n\r\n"));
+        assert!(keeps_alive(
+            b"HTTP/1.0 204 No Content\r\nConnection: keep-alive\r\n\r\n"
+        ));
+    }
+
+    #[test]
+    fn close_delimited_body() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\n\r\nhello".to_vec();
+        let stream = Cursor::new(b", world".to_vec());
+        let (res, rest) = parse_pipelined_response(buf, stream, url, &Method::GET).unwrap();
+        assert_eq!(res.body, b"hello, world");
+        assert!(rest.is_empty());
+        // The body only ended because the connection was closed.
+        assert!(keeps_alive(&res));
+        assert!(!can_reuse(&res, &rest, &Method::GET));
+    }
+
+    #[test]
+    fn reuse_needs_known_length_and_no_leftover() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let parse = |raw: &[u8]| {
+            parse_pipelined_response(raw.to_vec(), Cursor::new(vec![]), url.clone(), &Method::GET)
+                .unwrap()
+        };
+
+        let (res, rest) = parse(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+        assert!(can_reuse(&res, &rest, &Method::GET));
+        let (res, rest) = parse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n");
+        assert!(can_reuse(&res, &rest, &Method::GET));
+        let (res, rest) = parse(b"HTTP/1.1 204 No Content\r\n\r\n");
+        assert!(can_reuse(&res, &rest, &Method::GET));
+
+        let (res, rest) = parse(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello, world");
+        assert_eq!(res.body, b"hello");
+        assert_eq!(rest, b", world");
+        assert!(!can_reuse(&res, &rest, &Method::GET));
+    }
+
+    #[test]
+    fn head_response_has_no_body() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".to_vec();
+
+        // Reading the 100 bytes would fail, since the stream is closed.
+        let (res, rest) =
+            parse_pipelined_response(buf, Cursor::new(vec![]), url, &Method::HEAD).unwrap();
+        assert_eq!(res.headers["content-length"], "100");
+        assert!(res.body.is_empty());
+        assert!(rest.is_empty());
+        assert!(can_reuse(&res, &rest, &Method::HEAD));
+    }
+
+    #[test]
+    fn not_modified_has_no_body() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 304 Not Modified\r\nContent-Length: 5\r\n\r\n\
+            HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello"
+            .to_vec();
+
+        let (res, rest) =
+            parse_pipelined_response(buf, Cursor::new(vec![]), url, &Method::GET).unwrap();
+        assert_eq!(res.status, http::StatusCode::NOT_MODIFIED);
+        assert!(res.body.is_empty());
+        assert!(rest.starts_with(b"HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn chunked_body() {
+        let mut buf = b"5\r\nhello\r\n7\r\n, world\r\n0\r\n\r\n".to_vec();
+        let (body, end) = read_chunked_body(&mut buf, 0, &mut Cursor::new(vec![])).unwrap();
+        assert_eq!(body, b"hello, world");
+        assert_eq!(end, buf.len());
+    }
+
+    #[test]
+    fn chunked_body_with_extensions_and_trailers() {
+        let mut buf = b"5;name=value\r\nhello\r\n0;last\r\nExpires: never\r\n\r\nHTTP".to_vec();
+        let (body, end) = read_chunked_body(&mut buf, 0, &mut Cursor::new(vec![])).unwrap();
+        assert_eq!(body, b"hello");
+        assert_eq!(&buf[end..], b"HTTP");
+    }
+
+    #[test]
+    fn chunked_body_reads_from_stream() {
+        let mut buf = b"5\r\nhel".to_vec();
+        let mut stream = Cursor::new(b"lo\r\n0\r\n\r\n".to_vec());
+        let (body, _) = read_chunked_body(&mut buf, 0, &mut stream).unwrap();
+        assert_eq!(body, b"hello");
+    }
+
+    #[test]
+    fn chunked_body_missing_crlf() {
+        let mut buf = b"5;ext\r\nhello!!0\r\n\r\n".to_vec();
+        let err = read_chunked_body(&mut buf, 0, &mut Cursor::new(vec![])).unwrap_err();
+        assert!(matches!(err, ParseResponseError::InvalidChunk));
+    }
+
+    #[test]
+    fn empty_response() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let err =
+            parse_pipelined_response(vec![], Cursor::new(vec![]), url, &Method::GET).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseResponseError::TcpStreamClosedWithoutData
+        ));
+    }
+
+    #[test]
+    fn response_closed_during_head() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let stream = Cursor::new(b"HTTP/1.1 200 OK\r\nContent-Le".to_vec());
+        let err = parse_pipelined_response(vec![], stream, url, &Method::GET).unwrap_err();
+        assert!(matches!(err, ParseResponseError::TcpStreamClosed));
+    }
+
+    #[test]
+    fn response_timed_out() {
+        struct Stalled;
+
+        impl Read for Stalled {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::TimedOut.into())
+            }
+        }
+
+        let url = Url::parse("http://example.domain/").unwrap();
+        let err = parse_pipelined_response(vec![], Stalled, url.clone(), &Method::GET).unwrap_err();
+        assert!(matches!(err, ParseResponseError::TimedOut));
+
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel".to_vec();
+        let err = parse_pipelined_response(buf, Stalled, url, &Method::GET).unwrap_err();
+        assert!(matches!(err, ParseResponseError::TimedOut));
+    }
+
+    #[test]
+    fn response_with_only_headers() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let stream = Cursor::new(b"HTTP/1.1 200 OK\r\nX-Header: value\r\n\r\n".to_vec());
+        let (res, rest) = parse_pipelined_response(vec![], stream, url, &Method::GET).unwrap();
+        assert_eq!(res.status, http::StatusCode::OK);
+        assert_eq!(res.headers["x-header"], "value");
+        assert!(res.body.is_empty());
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn chunked_response_read_from_stream() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\none\r\n".to_vec();
+        let stream = Cursor::new(b"5\r\n, two\r\n7\r\n, three\r\n0\r\n\r\n".to_vec());
+        let (res, rest) = parse_pipelined_response(buf, stream, url, &Method::GET).unwrap();
+        assert_eq!(res.body, b"one, two, three");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn chunked_overrides_content_length() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nhello\r\n0\r\n\r\n\
+            HTTP/1.1 204 No Content\r\n\r\n"
+            .to_vec();
+        let (res, rest) =
+            parse_pipelined_response(buf, Cursor::new(vec![]), url, &Method::GET).unwrap();
+        assert_eq!(res.body, b"hello");
+        assert_eq!(rest, b"HTTP/1.1 204 No Content\r\n\r\n");
+    }
+
+    #[test]
+    fn response_too_large() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let mut buf = b"HTTP/1.1 200 OK\r\nX-Large: ".to_vec();
+        buf.resize(super::MAX_REQUEST_SIZE, b'a');
+        let stream = Cursor::new(vec![b'a'; super::REQUEST_BUFFER_SIZE]);
+        let err = parse_pipelined_response(buf, stream, url, &Method::GET).unwrap_err();
+        assert!(matches!(err, ParseResponseError::ResponseTooLarge));
+    }
+
+    #[test]
+    fn response_with_too_many_headers() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let mut buf = b"HTTP/1.1 200 OK\r\n".to_vec();
+        for i in 0..=super::MAX_HEADERS {
+            buf.extend(format!("X-Header-{}: value\r\n", i).as_bytes());
+        }
+        buf.extend(b"\r\n");
+        let err =
+            parse_pipelined_response(buf, Cursor::new(vec![]), url, &Method::GET).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseResponseError::HttpParseError(httparse::Error::TooManyHeaders)
+        ));
+    }
+
+    #[test]
+    fn invalid_content_length() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: twelve\r\n\r\n".to_vec();
+        let err =
+            parse_pipelined_response(buf, Cursor::new(vec![]), url, &Method::GET).unwrap_err();
+        assert!(matches!(err, ParseResponseError::InvalidContentLength));
+    }
+
+    #[test]
+    fn content_length_longer_than_stream() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".to_vec();
+        let stream = Cursor::new(b"short".to_vec());
+        let err = parse_pipelined_response(buf, stream, url, &Method::GET).unwrap_err();
+        assert!(matches!(err, ParseResponseError::TcpStreamClosed));
+    }
+
+    #[test]
+    fn content_length_too_large() {
+        let url = Url::parse("http://example.domain/").unwrap();
+        for length in [super::MAX_REQUEST_SIZE, usize::MAX] {
+            let buf = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", length);
+            let stream = Cursor::new(vec![b'a'; super::REQUEST_BUFFER_SIZE]);
+            let err = parse_pipelined_response(buf.into_bytes(), stream, url.clone(), &Method::GET)
+                .unwrap_err();
+            assert!(matches!(err, ParseResponseError::ResponseTooLarge));
+        }
+    }
+}
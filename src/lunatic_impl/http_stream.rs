@@ -1,19 +1,55 @@
-use std::io::{Read, Write};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::time::Duration;
 
 use lunatic::net::{TcpStream, TlsStream};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::error::Kind;
-
+/// A connection to an HTTP server.
+///
+/// See [`Client::execute_with_stream`](crate::Client::execute_with_stream).
 #[derive(Clone, Serialize, Deserialize)]
 pub enum HttpStream {
+    /// A plain TCP connection, used for `http` urls.
     Tcp(TcpStream),
+    /// A TLS connection, used for `https` urls.
     Tls(TlsStream),
 }
 
+impl fmt::Debug for HttpStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpStream::Tcp(_) => f.pad("HttpStream::Tcp"),
+            HttpStream::Tls(_) => f.pad("HttpStream::Tls"),
+        }
+    }
+}
+
 impl HttpStream {
+    /// Connect to the host and port of `url`, over TLS for `https` urls.
     pub fn connect(url: Url) -> crate::Result<HttpStream> {
+        HttpStream::connect_timeout(url, None)
+    }
+
+    /// Connect to the host and port of `url` like [`HttpStream::connect`],
+    /// failing if a plain TCP connection isn't established within `timeout`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                server.address = url.host_str(),
+                server.port = url.port_or_known_default(),
+            )
+        )
+    )]
+    pub(crate) fn connect_timeout(
+        url: Url,
+        timeout: Option<Duration>,
+    ) -> crate::Result<HttpStream> {
         let protocol = url.scheme();
         if protocol == "https" {
             let conn_str = format!("{}", url.host().unwrap());
@@ -21,23 +57,67 @@ impl HttpStream {
                 Ok(stream) => Ok(HttpStream::Tls(stream)),
                 Err(e) => {
                     lunatic_log::error!("Failed to connect via TLS {:?}", e);
-                    Err(crate::Error::new(
-                        Kind::Builder,
-                        Some("Failed to connect".to_string()),
-                    ))
+                    Err(crate::error::connect("Failed to connect"))
                 }
             };
         }
         let conn_str = format!("{}:{}", url.host().unwrap(), url.port().unwrap_or(80));
         lunatic_log::debug!("Connecting {:?} | {:?}", protocol, conn_str);
-        match TcpStream::connect(conn_str) {
+        HttpStream::connect_tcp(conn_str, timeout)
+    }
+
+    /// Open a plain TCP connection to the first of `addrs` that accepts it.
+    pub(crate) fn connect_addrs(
+        addrs: &[SocketAddr],
+        timeout: Option<Duration>,
+    ) -> crate::Result<HttpStream> {
+        let mut last_err = None;
+        for addr in addrs {
+            lunatic_log::debug!("Connecting {:?}", addr);
+            match HttpStream::connect_tcp(addr.to_string(), timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| crate::error::connect("no addresses to connect to")))
+    }
+
+    /// The address of the server, if the stream exposes it.
+    pub(crate) fn peer_addr(&self) -> Option<SocketAddr> {
+        match self {
+            HttpStream::Tcp(stream) => stream.peer_addr().ok(),
+            HttpStream::Tls(_) => None,
+        }
+    }
+
+    /// Set the timeout of every read from and write to the stream, or clear
+    /// it with `None`.
+    pub(crate) fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            HttpStream::Tcp(stream) => {
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)
+            }
+            HttpStream::Tls(stream) => {
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)
+            }
+        }
+    }
+
+    fn connect_tcp(conn_str: String, timeout: Option<Duration>) -> crate::Result<HttpStream> {
+        let stream = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(conn_str, timeout),
+            None => TcpStream::connect(conn_str),
+        };
+        match stream {
             Ok(stream) => Ok(HttpStream::Tcp(stream)),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                Err(crate::error::connect(crate::error::TimedOut))
+            }
             Err(e) => {
                 lunatic_log::error!("Failed to connect via TCP {:?}", e);
-                Err(crate::Error::new(
-                    Kind::Builder,
-                    Some("Failed to connect".to_string()),
-                ))
+                Err(crate::error::connect(e))
             }
         }
     }
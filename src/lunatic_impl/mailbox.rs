@@ -0,0 +1,210 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::Duration;
+
+use http::header::{HeaderName, HeaderValue};
+use lunatic::abstract_process;
+use lunatic::process::{ProcessRef, StartProcess};
+use serde::{Deserialize, Serialize};
+
+use super::{Body, Client, HttpResponse, Request};
+use crate::{Method, Url};
+
+/// A `Client` running in its own lunatic process.
+///
+/// A `Client` can't be shared across the process boundary, so processes that
+/// can only communicate through their `Mailbox` send a [`SendRequest`] to a
+/// `MailboxClient` instead, and get the `HttpResponse` back as the reply.
+///
+/// The process builds its `Client` with the [`ClientFactory`] it is started
+/// with, see [`MailboxClient::start_with`] and
+/// [`MailboxClient::start_link_with`].
+///
+/// # Example
+///
+/// ```no_run
+/// use nightfly::{ClientFactory, MailboxClient, MailboxClientHandler, SendRequest};
+///
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = MailboxClient::start_link_with(ClientFactory::default(), None);
+///
+/// let req = nightfly::Client::new().get("http://httpbin.org/uuid").build()?;
+/// let res = client.send_request(SendRequest::from(req))?;
+/// println!("status: {}", res.status());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MailboxClient {
+    client: Client,
+}
+
+#[abstract_process(visibility = pub)]
+impl MailboxClient {
+    #[init]
+    fn init(_: ProcessRef<Self>, arg: StartArg) -> Self {
+        MailboxClient {
+            client: arg.into_factory().build(),
+        }
+    }
+
+    /// Send the request, replying with the response or the error.
+    #[handle_request]
+    fn send_request(&mut self, request: SendRequest) -> crate::Result<HttpResponse> {
+        request
+            .into_request()
+            .and_then(|req| self.client.execute(req))
+    }
+}
+
+impl MailboxClient {
+    /// Start a `MailboxClient` process that builds its `Client` with
+    /// `factory`, optionally registered under `name`.
+    pub fn start_with(factory: ClientFactory, name: Option<&str>) -> ProcessRef<MailboxClient> {
+        MailboxClient::start(StartArg::new(factory), name)
+    }
+
+    /// Start a `MailboxClient` process like [`MailboxClient::start_with`],
+    /// linked to the calling process.
+    pub fn start_link_with(
+        factory: ClientFactory,
+        name: Option<&str>,
+    ) -> ProcessRef<MailboxClient> {
+        MailboxClient::start_link(StartArg::new(factory), name)
+    }
+}
+
+/// Builds the `Client` of a [`MailboxClient`].
+///
+/// A `Client` can't be sent to another process, so a `MailboxClient` is
+/// started with the function that builds its `Client` instead. The default
+/// factory uses `Client::new()`.
+///
+/// A `ClientFactory` is only passed to the `MailboxClient` processes started
+/// by this crate and can't be sent in messages of its own.
+///
+/// # Example
+///
+/// ```no_run
+/// use nightfly::{ClientFactory, MailboxClient};
+///
+/// fn build_client() -> nightfly::Client {
+///     nightfly::Client::builder()
+///         .timeout(std::time::Duration::from_secs(10))
+///         .build()
+///         .expect("client configuration is valid")
+/// }
+///
+/// let client = MailboxClient::start_link_with(ClientFactory::new(build_client), None);
+/// ```
+#[derive(Clone, Copy)]
+pub struct ClientFactory(fn() -> Client);
+
+impl ClientFactory {
+    /// A factory that builds the `Client` by calling `build`.
+    ///
+    /// `build` runs in the `MailboxClient` process, so a panic in it fails
+    /// that process.
+    pub fn new(build: fn() -> Client) -> ClientFactory {
+        ClientFactory(build)
+    }
+
+    fn build(&self) -> Client {
+        (self.0)()
+    }
+}
+
+impl Default for ClientFactory {
+    fn default() -> ClientFactory {
+        ClientFactory(Client::new)
+    }
+}
+
+impl fmt::Debug for ClientFactory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("ClientFactory")
+    }
+}
+
+/// The argument a [`MailboxClient`] process is started with.
+///
+/// It carries the function of a [`ClientFactory`] as its index in the
+/// function table, which is only meaningful to processes running the same
+/// module. It is built only by [`MailboxClient::start_with`] and
+/// [`MailboxClient::start_link_with`], and is never meant to be deserialized
+/// from anything but the message they send.
+#[derive(Serialize, Deserialize)]
+pub struct StartArg {
+    factory: usize,
+}
+
+impl StartArg {
+    fn new(factory: ClientFactory) -> StartArg {
+        StartArg {
+            factory: factory.0 as usize,
+        }
+    }
+
+    fn into_factory(self) -> ClientFactory {
+        // Safety: `StartArg` is only built from a `ClientFactory` in
+        // `StartArg::new`, and all processes run the same module, so the index
+        // refers to the same `fn() -> Client` in the started process.
+        ClientFactory(unsafe { std::mem::transmute::<usize, fn() -> Client>(self.factory) })
+    }
+}
+
+/// A serializable `Request`, sent to a [`MailboxClient`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendRequest {
+    method: String,
+    url: Url,
+    headers: Vec<(String, Vec<u8>)>,
+    body: Option<Body>,
+    timeout: Option<Duration>,
+}
+
+impl SendRequest {
+    /// Convert back into a `Request`.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the method or a header is invalid.
+    pub fn into_request(self) -> crate::Result<Request> {
+        let method = Method::from_bytes(self.method.as_bytes()).map_err(crate::error::builder)?;
+        let mut req = Request::new(method, self.url);
+        for (name, value) in self.headers {
+            req.headers_mut().append(
+                HeaderName::try_from(name).map_err(crate::error::builder)?,
+                HeaderValue::try_from(value).map_err(crate::error::builder)?,
+            );
+        }
+        *req.body_mut() = self.body;
+        *req.timeout_mut() = self.timeout;
+        Ok(req)
+    }
+}
+
+impl From<Request> for SendRequest {
+    fn from(req: Request) -> SendRequest {
+        let headers = req
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.as_str().to_owned(), value.as_bytes().to_vec()))
+            .collect();
+        SendRequest {
+            method: req.method().as_str().to_owned(),
+            url: req.url().clone(),
+            headers,
+            body: req.body().cloned(),
+            timeout: req.timeout().copied(),
+        }
+    }
+}
+
+impl TryFrom<SendRequest> for Request {
+    type Error = crate::Error;
+
+    fn try_from(req: SendRequest) -> crate::Result<Request> {
+        req.into_request()
+    }
+}
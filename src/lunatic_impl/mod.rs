@@ -1,16 +1,29 @@
 pub use self::body::Body;
 pub use self::client::{Client, ClientBuilder};
+#[cfg(any(
+    feature = "gzip",
+    feature = "deflate",
+    feature = "brotli",
+    feature = "zstd"
+))]
+pub use self::compression::CompressionAlgorithm;
+pub use self::http_stream::HttpStream;
+pub use self::mailbox::{ClientFactory, MailboxClient, MailboxClientHandler, SendRequest};
 pub use self::request::{Request, RequestBuilder};
 pub use self::response::HttpResponse;
 // pub use self::upgrade::Upgraded;
 
+#[doc(hidden)]
+pub use self::decoder::fuzz_parse_response;
 #[cfg(feature = "blocking")]
 pub(crate) use self::decoder::Decoder;
 
 pub mod body;
 pub mod client;
+mod compression;
 pub mod decoder;
 mod http_stream;
+mod mailbox;
 #[cfg(feature = "multipart")]
 pub mod multipart;
 pub(crate) mod request;
@@ -1,25 +1,37 @@
 use std::convert::TryFrom;
 use std::fmt;
-use std::io::Write;
+use std::sync::Arc;
 use std::time::Duration;
 
-use base64::write::EncoderWriter as Base64Encoder;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "json")]
 use serde_json;
 
 use super::client::Client;
+use super::compression::CompressionAlgorithm;
+use super::mailbox::{MailboxClient, MailboxClientHandler, SendRequest};
 #[cfg(feature = "multipart")]
 use super::multipart;
 use super::response::HttpResponse;
 #[cfg(feature = "multipart")]
 use crate::header::CONTENT_LENGTH;
-use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
-use crate::{Body, Method, Url};
-use http::{request::Parts, Request as HttpRequest, Version};
+use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use crate::util::base64_encode;
+use crate::{redirect, Body, Method, Url, Version};
+use http::{request::Parts, Extensions, Request as HttpRequest};
+use lunatic::process::ProcessRef;
 
 /// A request which can be executed with `Client::execute()`.
-#[derive(Clone)]
+///
+/// Cloning a request does not clone its [`extensions`](Request::extensions),
+/// since they may hold values that can't be cloned.
+///
+/// A request can be serialized, so it can be sent to other lunatic processes.
+/// It is serialized as a [`SendRequest`], which keeps the method, url,
+/// headers, body and timeout. The extensions, HTTP version and any redirect
+/// override are not included.
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "SendRequest", into = "SendRequest")]
 pub struct Request {
     pub(crate) method: Method,
     pub(crate) url: Url,
@@ -27,6 +39,9 @@ pub struct Request {
     pub(crate) body: Option<Body>,
     pub(crate) timeout: Option<Duration>,
     pub(crate) version: Version,
+    pub(crate) extensions: Extensions,
+    // Overrides the redirect policy of the client.
+    pub(crate) redirect_policy: Option<Arc<redirect::Policy>>,
 }
 
 /// A builder to construct the properties of a `Request`.
@@ -36,6 +51,7 @@ pub struct Request {
 pub struct RequestBuilder {
     client: Client,
     request: crate::Result<Request>,
+    compression: Option<CompressionAlgorithm>,
 }
 
 impl Request {
@@ -49,6 +65,8 @@ impl Request {
             body: None,
             timeout: None,
             version: Version::default(),
+            extensions: Extensions::new(),
+            redirect_policy: None,
         }
     }
 
@@ -124,6 +142,18 @@ impl Request {
         &mut self.version
     }
 
+    /// Get the extensions.
+    #[inline]
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Get a mutable reference to the extensions.
+    #[inline]
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     // /// Attempt to clone the request.
     // ///
     // /// `None` is returned if the request can not be cloned, i.e. if the body is a stream.
@@ -163,7 +193,11 @@ impl Request {
 
 impl RequestBuilder {
     pub(super) fn new(client: Client, request: crate::Result<Request>) -> RequestBuilder {
-        let mut builder = RequestBuilder { client, request };
+        let mut builder = RequestBuilder {
+            client,
+            request,
+            compression: None,
+        };
 
         let auth = builder
             .request
@@ -250,15 +284,11 @@ impl RequestBuilder {
         U: fmt::Display,
         P: fmt::Display,
     {
-        let mut header_value = b"Basic ".to_vec();
-        {
-            let mut encoder = Base64Encoder::new(&mut header_value, base64::STANDARD);
-            // The unwraps here are fine because Vec::write* is infallible.
-            write!(encoder, "{}:", username).unwrap();
-            if let Some(password) = password {
-                write!(encoder, "{}", password).unwrap();
-            }
-        }
+        let credentials = match password {
+            Some(password) => format!("{}:{}", username, password),
+            None => format!("{}:", username),
+        };
+        let header_value = format!("Basic {}", base64_encode(credentials.as_bytes()));
 
         self.header_sensitive(crate::header::AUTHORIZATION, header_value, true)
     }
@@ -272,6 +302,47 @@ impl RequestBuilder {
         self.header_sensitive(crate::header::AUTHORIZATION, header_value, true)
     }
 
+    /// Set the request body along with its `Content-Type` header.
+    ///
+    /// See [`ContentType`](crate::header::ContentType) for common values.
+    pub fn body_with_content_type<T: Into<Body>>(
+        self,
+        body: T,
+        content_type: HeaderValue,
+    ) -> RequestBuilder {
+        self.header(CONTENT_TYPE, content_type).body(body)
+    }
+
+    /// Add extensions to this request.
+    ///
+    /// Values in `extensions` replace any of the same type already set on the
+    /// request. They can be read back with [`Request::extensions`].
+    ///
+    /// ```rust
+    /// # use nightfly::Error;
+    /// #
+    /// # fn run() -> Result<(), Error> {
+    /// #[derive(Clone, Copy)]
+    /// struct RetryCount(u32);
+    ///
+    /// let mut extensions = http::Extensions::new();
+    /// extensions.insert(RetryCount(3));
+    ///
+    /// let client = nightfly::Client::new();
+    /// let req = client.get("http://httpbin.org/get")
+    ///     .with_extensions(extensions)
+    ///     .build()?;
+    /// assert_eq!(req.extensions().get::<RetryCount>().unwrap().0, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_extensions(mut self, extensions: Extensions) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.extensions_mut().extend(extensions);
+        }
+        self
+    }
+
     /// Set a body that can be turned into a `Body`
     pub fn body<T: Into<Body>>(mut self, body: T) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -280,17 +351,45 @@ impl RequestBuilder {
         self
     }
 
+    /// Send the body of a previous response as the body of this request.
+    ///
+    /// The `Content-Type` header of `response`, if any, is copied onto this
+    /// request.
+    ///
+    /// ```rust
+    /// # use nightfly::Error;
+    /// #
+    /// # fn run() -> Result<(), Error> {
+    /// let client = nightfly::Client::new();
+    /// let image = client.get("http://httpbin.org/image/png").send()?;
+    /// let res = client.post("http://httpbin.org/post")
+    ///     .pipe(image)
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pipe(mut self, response: HttpResponse) -> RequestBuilder {
+        if let Some(content_type) = response.headers.get(CONTENT_TYPE) {
+            self = self.header(CONTENT_TYPE, content_type.clone());
+        }
+        self.body(response)
+    }
+
     /// Set the request body as json.
     pub fn json<T: Serialize>(mut self, body: T) -> RequestBuilder {
+        let mut error = None;
         if let Ok(ref mut req) = self.request {
             req.headers_mut().append(
                 "content-type",
                 HeaderValue::from_str("application/json").unwrap(),
             );
-            *req.body_mut() = match Body::json(body) {
-                Ok(d) => Some(d),
-                Err(_) => None,
-            };
+            match Body::json(body) {
+                Ok(d) => *req.body_mut() = Some(d),
+                Err(err) => error = Some(err),
+            }
+        }
+        if let Some(err) = error {
+            self.request = Err(err);
         }
         self
     }
@@ -308,9 +407,10 @@ impl RequestBuilder {
 
     /// Enables a request timeout.
     ///
-    /// The timeout is applied from when the request starts connecting until the
-    /// response body has finished. It affects only this request and overrides
-    /// the timeout configured using `ClientBuilder::timeout()`.
+    /// The timeout is applied to every read from and write to the connection
+    /// while the request is sent and its response is read. It affects only
+    /// this request and overrides the timeout configured using
+    /// `ClientBuilder::timeout()`.
     pub fn timeout(mut self, timeout: Duration) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
             *req.timeout_mut() = Some(timeout);
@@ -396,7 +496,35 @@ impl RequestBuilder {
         self
     }
 
+    /// Don't follow redirects for this request.
+    ///
+    /// This overrides the redirect policy of the client, as if it was built
+    /// with `redirect::Policy::none()`. A redirect response is returned as
+    /// is.
+    pub fn no_redirect(mut self) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.redirect_policy = Some(Arc::new(redirect::Policy::none()));
+        }
+        self
+    }
+
+    /// Follow at most `max` redirects for this request.
+    ///
+    /// This overrides the redirect policy of the client with
+    /// `redirect::Policy::limited(max)`.
+    pub fn max_redirects(mut self, max: usize) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.redirect_policy = Some(Arc::new(redirect::Policy::limited(max)));
+        }
+        self
+    }
+
     /// Set HTTP version
+    ///
+    /// Requests are sent over HTTP/1, so only `Version::HTTP_09`,
+    /// `Version::HTTP_10` and `Version::HTTP_11` are supported. Sending a
+    /// request with `Version::HTTP_2` or `Version::HTTP_3` fails with a
+    /// builder error.
     pub fn version(mut self, version: Version) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
             req.version = version;
@@ -494,10 +622,40 @@ impl RequestBuilder {
         self
     }
 
+    /// Compress the request body with the given algorithm before sending.
+    ///
+    /// The body is compressed when the request is built, and the matching
+    /// `Content-Encoding` header is set. Empty bodies are sent as-is.
+    ///
+    /// # Optional
+    ///
+    /// This requires at least one of the optional `gzip`, `deflate`, `brotli`
+    /// or `zstd` features to be enabled, and each algorithm requires its own
+    /// feature.
+    #[cfg(any(
+        feature = "gzip",
+        feature = "deflate",
+        feature = "brotli",
+        feature = "zstd"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "gzip",
+            feature = "deflate",
+            feature = "brotli",
+            feature = "zstd"
+        )))
+    )]
+    pub fn compress_body(mut self, algorithm: CompressionAlgorithm) -> RequestBuilder {
+        self.compression = Some(algorithm);
+        self
+    }
+
     /// Build a `Request`, which can be inspected, modified and executed with
     /// `Client::execute()`.
     pub fn build(self) -> crate::Result<Request> {
-        self.request
+        compress_request(self.request?, self.compression)
     }
 
     /// Constructs the Request and sends it to the target URL, returning a
@@ -521,42 +679,65 @@ impl RequestBuilder {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn send(mut self) -> Result<HttpResponse, crate::Error> {
-        match self.request {
-            Ok(req) => self.client.execute_request(req),
-            Err(err) => Err(err),
+    pub fn send(self) -> crate::Result<HttpResponse> {
+        let RequestBuilder {
+            mut client,
+            request,
+            compression,
+        } = self;
+        client.execute_request(compress_request(request?, compression)?)
+    }
+
+    /// Constructs the Request and sends it through a [`MailboxClient`]
+    /// process, returning its Response.
+    ///
+    /// The request is serialized and sent to the `MailboxClient`'s mailbox,
+    /// so it is executed by the `Client` of that process rather than this
+    /// builder's. See [`MailboxClient`] for why this is useful.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the request could not be built, or with the
+    /// error of the `MailboxClient` if it failed to send it.
+    ///
+    /// [`MailboxClient`]: crate::MailboxClient
+    pub fn send_via(self, process: ProcessRef<MailboxClient>) -> crate::Result<HttpResponse> {
+        let req = self.build()?;
+        process.send_request(SendRequest::from(req))
+    }
+}
+
+/// Cloning a `RequestBuilder` clones its request, including the body.
+///
+/// If building the request has already failed, the clone carries a copy of
+/// the error with the same kind and url, whose source is replaced by its
+/// message.
+impl Clone for RequestBuilder {
+    fn clone(&self) -> RequestBuilder {
+        RequestBuilder {
+            client: self.client.clone(),
+            request: match self.request {
+                Ok(ref req) => Ok(req.clone()),
+                Err(ref err) => Err(err.clone_lossy()),
+            },
+            compression: self.compression,
         }
     }
+}
 
-    // /// Attempt to clone the RequestBuilder.
-    // ///
-    // /// `None` is returned if the RequestBuilder can not be cloned,
-    // /// i.e. if the request body is a stream.
-    // ///
-    // /// # Examples
-    // ///
-    // /// ```
-    // /// # use nightfly::Error;
-    // /// #
-    // /// # fn run() -> Result<(), Error> {
-    // /// let client = nightfly::Client::new();
-    // /// let builder = client.post("http://httpbin.org/post")
-    // ///     .body("from a &str!");
-    // /// let clone = builder.try_clone();
-    // /// assert!(clone.is_some());
-    // /// # Ok(())
-    // /// # }
-    // /// ```
-    // pub fn try_clone(&self) -> Option<RequestBuilder> {
-    //     self.request
-    //         .as_ref()
-    //         .ok()
-    //         .and_then(|req| req.try_clone())
-    //         .map(|req| RequestBuilder {
-    //             client: self.client.clone(),
-    //             request: Ok(req),
-    //         })
-    // }
+impl Clone for Request {
+    fn clone(&self) -> Request {
+        Request {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+            timeout: self.timeout,
+            version: self.version,
+            extensions: Extensions::new(),
+            redirect_policy: self.redirect_policy.clone(),
+        }
+    }
 }
 
 impl fmt::Debug for Request {
@@ -575,6 +756,26 @@ impl fmt::Debug for RequestBuilder {
     }
 }
 
+fn compress_request(
+    mut req: Request,
+    compression: Option<CompressionAlgorithm>,
+) -> crate::Result<Request> {
+    let algorithm = match compression {
+        Some(algorithm) => algorithm,
+        None => return Ok(req),
+    };
+    let compressed = match req.body() {
+        Some(body) if body.len() > 0 => algorithm
+            .compress(&body.clone().inner())
+            .map_err(crate::error::body)?,
+        _ => return Ok(req),
+    };
+    *req.body_mut() = Some(Body::from(compressed));
+    req.headers_mut()
+        .insert(CONTENT_ENCODING, algorithm.content_encoding());
+    Ok(req)
+}
+
 fn fmt_request_fields<'a, 'b>(
     f: &'a mut fmt::DebugStruct<'a, 'b>,
     req: &Request,
@@ -625,6 +826,7 @@ where
             uri,
             headers,
             version,
+            extensions,
             ..
         } = parts;
         let url = Url::parse(&uri.to_string()).map_err(crate::error::builder)?;
@@ -634,7 +836,9 @@ where
             headers,
             body: Some(body.into()),
             timeout: None,
-            version,
+            version: version.into(),
+            extensions,
+            redirect_policy: None,
         })
     }
 }
@@ -649,17 +853,19 @@ impl TryFrom<Request> for HttpRequest<Body> {
             headers,
             body,
             version,
+            extensions,
             ..
         } = req;
 
         let mut req = HttpRequest::builder()
-            .version(version)
+            .version(version.into())
             .method(method)
             .uri(url.as_str())
             .body(body.unwrap_or_else(Body::empty))
             .map_err(crate::error::builder)?;
 
         *req.headers_mut() = headers;
+        *req.extensions_mut() = extensions;
         Ok(req)
     }
 }
@@ -672,6 +878,59 @@ mod tests {
     use std::collections::BTreeMap;
     use std::convert::TryFrom;
 
+    #[test]
+    fn serde_roundtrip() {
+        use super::{Body, HeaderValue};
+        use std::time::Duration;
+
+        let mut req = Request::new(Method::PATCH, "http://localhost/items/1".parse().unwrap());
+        req.headers_mut()
+            .append("x-tag", HeaderValue::from_static("one"));
+        req.headers_mut()
+            .append("x-tag", HeaderValue::from_static("two"));
+        *req.body_mut() = Some(Body::from(b"payload".to_vec()));
+        *req.timeout_mut() = Some(Duration::from_secs(5));
+
+        let json = serde_json::to_vec(&req).unwrap();
+        let de: Request = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(de.method(), Method::PATCH);
+        assert_eq!(de.url().as_str(), "http://localhost/items/1");
+        assert_eq!(de.headers(), req.headers());
+        assert_eq!(de.body().cloned().unwrap().inner(), b"payload");
+        assert_eq!(de.timeout(), Some(&Duration::from_secs(5)));
+
+        let invalid = r#"{"method":"BAD METHOD","url":"http://localhost/","headers":[],"body":null,"timeout":null}"#;
+        assert!(serde_json::from_str::<Request>(invalid).is_err());
+    }
+
+    #[test]
+    fn pipe_response_body() {
+        use super::{HeaderMap, HttpResponse, CONTENT_TYPE};
+        use crate::{StatusCode, Url};
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, "image/png".parse().unwrap());
+        let response = HttpResponse {
+            body: b"pipe me".to_vec(),
+            status: StatusCode::OK,
+            version: Version::HTTP_11,
+            headers,
+            url: Url::parse("https://google.com/image").unwrap(),
+            remote_addr: None,
+        };
+
+        let client = Client::new();
+        let req = client
+            .post("https://google.com/")
+            .pipe(response)
+            .build()
+            .expect("request is valid");
+
+        assert_eq!(req.headers()[CONTENT_TYPE], "image/png");
+        assert_eq!(req.body().unwrap().clone().inner(), b"pipe me");
+    }
+
     #[test]
     fn add_query_append() {
         let client = Client::new();
@@ -777,36 +1036,112 @@ mod tests {
         assert_eq!(req.url().as_str(), "https://google.com/");
     }
 
-    // #[test]
-    // fn try_clone_reusable() {
-    //     let client = Client::new();
-    //     let builder = client
-    //         .post("http://httpbin.org/post")
-    //         .header("foo", "bar")
-    //         .text("from a &str!");
-    //     let req = builder
-    //         .try_clone()
-    //         .expect("clone successful")
-    //         .build()
-    //         .expect("request is valid");
-    //     assert_eq!(req.url().as_str(), "http://httpbin.org/post");
-    //     assert_eq!(req.method(), Method::POST);
-    //     assert_eq!(req.headers()["foo"], "bar");
-    // }
+    #[test]
+    fn body_with_content_type() {
+        use crate::header::{ContentType, CONTENT_TYPE};
 
-    // #[test]
-    // fn try_clone_no_body() {
-    //     let client = Client::new();
-    //     let builder = client.get("http://httpbin.org/get");
-    //     let req = builder
-    //         .try_clone()
-    //         .expect("clone successful")
-    //         .build()
-    //         .expect("request is valid");
-    //     assert_eq!(req.url().as_str(), "http://httpbin.org/get");
-    //     assert_eq!(req.method(), Method::GET);
-    //     assert!(req.body().is_none());
-    // }
+        let client = Client::new();
+        let req = client
+            .post("https://google.com/")
+            .body_with_content_type("{}".to_string(), ContentType::JSON)
+            .build()
+            .expect("request is valid");
+
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(req.body().unwrap().clone().inner(), b"{}");
+    }
+
+    #[test]
+    fn url_mut_rewrites_url() {
+        let client = Client::new();
+        let mut req = client
+            .get("https://google.com/search?q=rust")
+            .build()
+            .expect("request is valid");
+
+        req.url_mut().set_host(Some("localhost")).unwrap();
+        assert_eq!(req.url().as_str(), "https://localhost/search?q=rust");
+    }
+
+    #[test]
+    fn clone_reusable() {
+        let client = Client::new();
+        let builder = client
+            .post("http://httpbin.org/post")
+            .header("foo", "bar")
+            .text("from a &str!");
+        let req = builder.clone().build().expect("request is valid");
+        assert_eq!(req.url().as_str(), "http://httpbin.org/post");
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.headers()["foo"], "bar");
+        assert_eq!(req.body().unwrap().clone().inner(), b"from a &str!");
+
+        // The original can still be used.
+        let req = builder.build().expect("request is valid");
+        assert_eq!(req.headers()["foo"], "bar");
+    }
+
+    #[test]
+    fn clone_no_body() {
+        let client = Client::new();
+        let builder = client.get("http://httpbin.org/get");
+        let req = builder.clone().build().expect("request is valid");
+        assert_eq!(req.url().as_str(), "http://httpbin.org/get");
+        assert_eq!(req.method(), Method::GET);
+        assert!(req.body().is_none());
+    }
+
+    #[test]
+    fn no_redirect_overrides_client_policy() {
+        let client = Client::new();
+        let req = client
+            .get("http://httpbin.org/redirect/1")
+            .build()
+            .expect("request is valid");
+        assert!(req.redirect_policy.is_none());
+
+        let req = client
+            .get("http://httpbin.org/redirect/1")
+            .no_redirect()
+            .build()
+            .expect("request is valid");
+        let policy = req.redirect_policy.as_ref().expect("policy is set");
+        let next = crate::Url::parse("http://httpbin.org/get").unwrap();
+        assert!(matches!(
+            policy.check(crate::StatusCode::FOUND, &next, &[req.url().clone()]),
+            crate::redirect::ActionKind::Stop
+        ));
+    }
+
+    #[test]
+    fn max_redirects_overrides_client_policy() {
+        let client = Client::new();
+        let req = client
+            .get("http://httpbin.org/redirect/3")
+            .max_redirects(2)
+            .build()
+            .expect("request is valid");
+        let policy = req.redirect_policy.as_ref().expect("policy is set");
+        let next = crate::Url::parse("http://httpbin.org/redirect/1").unwrap();
+        let first = crate::Url::parse("http://httpbin.org/redirect/3").unwrap();
+        let second = crate::Url::parse("http://httpbin.org/redirect/2").unwrap();
+        assert!(matches!(
+            policy.check(crate::StatusCode::FOUND, &next, &[first.clone()]),
+            crate::redirect::ActionKind::Follow
+        ));
+        assert!(matches!(
+            policy.check(crate::StatusCode::FOUND, &next, &[first, second]),
+            crate::redirect::ActionKind::Error(_)
+        ));
+    }
+
+    #[test]
+    fn clone_keeps_error() {
+        let client = Client::new();
+        let builder = client.get("not a url");
+        let err = builder.clone().build().unwrap_err();
+        assert!(err.is_builder());
+    }
 
     #[test]
     fn convert_url_authority_into_basic_auth() {
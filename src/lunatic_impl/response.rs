@@ -1,62 +1,38 @@
 use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt;
+use std::io::{BufRead, BufReader, Cursor};
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::time::Duration;
 
 use bytes::Bytes;
 use encoding_rs::{Encoding, UTF_8};
-use http::{HeaderMap, HeaderValue, StatusCode, Version};
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue, StatusCode};
 use mime::Mime;
 use serde::de::DeserializeOwned;
 #[cfg(feature = "json")]
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "json")]
 use serde_json;
 use url::Url;
 
-use super::body::Body;
+use super::body::{Body, BodyReader};
 use super::decoder::{Accepts, Decoder};
 #[cfg(feature = "cookies")]
 use crate::cookie;
+use crate::header::{HeaderExt, RetryAfter};
 use crate::response::ResponseUrl;
-
-/// Extra information about the transport when an HttpConnector is used.
-///
-/// # Example
-///
-/// ```
-/// # async fn doc() -> hyper::Result<()> {
-/// use hyper::Uri;
-/// use hyper::client::{Client, connect::HttpInfo};
-///
-/// let client = Client::new();
-/// let uri = Uri::from_static("http://example.com");
-///
-/// let res = client.get(uri).await?;
-/// res
-///     .extensions()
-///     .get::<HttpInfo>()
-///     .map(|info| {
-///         println!("remote addr = {}", info.remote_addr());
-///     });
-/// # Ok(())
-/// # }
-/// ```
-///
-/// # Note
-///
-/// If a different connector is used besides [`HttpConnector`](HttpConnector),
-/// this value will not exist in the extensions. Consult that specific
-/// connector to see what "extra" information it might provide to responses.
-#[derive(Clone, Debug)]
-pub struct HttpInfo {
-    remote_addr: SocketAddr,
-    local_addr: SocketAddr,
-}
+use crate::Version;
 
 /// A Response to a submitted `Request`.
+///
+/// A response can be serialized, so it can be sent to other lunatic
+/// processes.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedResponse", into = "SerializedResponse")]
 pub struct HttpResponse {
     /// body of response
     pub body: Vec<u8>,
@@ -69,27 +45,40 @@ pub struct HttpResponse {
     /// The response's headers
     pub headers: HeaderMap<HeaderValue>,
 
+    /// The final url, only readable through [`HttpResponse::url`].
+    ///
+    /// Keeping it private means a response can't be constructed outside of
+    /// this crate, even with every field given:
+    ///
+    /// ```compile_fail
+    /// let res = nightfly::HttpResponse {
+    ///     body: Vec::new(),
+    ///     status: nightfly::StatusCode::OK,
+    ///     version: nightfly::Version::HTTP_11,
+    ///     headers: Default::default(),
+    ///     url: "http://localhost/".parse().unwrap(),
+    ///     remote_addr: None,
+    /// };
+    /// ```
     pub(super) url: Url,
+
+    /// The address of the server, only readable through
+    /// [`HttpResponse::remote_addr`].
+    pub(super) remote_addr: Option<SocketAddr>,
 }
 
 impl HttpResponse {
-    pub(super) fn new(
-        res: http::Response<Vec<u8>>,
-        url: Url,
-        accepts: Accepts,
-        timeout: Option<Duration>,
-    ) -> HttpResponse {
-        let (mut parts, body) = res.into_parts();
-        let decoder = Decoder::detect(&mut parts.headers, body, accepts);
-        let body = decoder.decode();
-
-        HttpResponse {
-            body,
-            url,
-            version: parts.version,
-            status: parts.status,
-            headers: parts.headers,
-        }
+    /// Decompress the body according to its `Content-Encoding`, if it is one
+    /// of the `accepts` encodings.
+    ///
+    /// The `Content-Encoding` and `Content-Length` headers are removed from a
+    /// decompressed response, since they describe the body on the wire.
+    pub(super) fn decode(mut self, accepts: Accepts) -> crate::Result<HttpResponse> {
+        let body = std::mem::take(&mut self.body);
+        self.body = Decoder::detect(&mut self.headers, body, accepts)
+            .decode()
+            .map_err(crate::error::decode_io)?;
+        Ok(self)
     }
 
     /// Get the `StatusCode` of this `Response`.
@@ -98,6 +87,33 @@ impl HttpResponse {
         self.status
     }
 
+    /// Returns `true` if the status is informational (`1xx`).
+    #[inline]
+    pub fn is_informational(&self) -> bool {
+        self.status.is_informational()
+    }
+
+    /// Returns `true` if the status is a success (`2xx`).
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// Returns `true` if the status is a redirection (`3xx`).
+    ///
+    /// This is useful to check whether a response is still a redirect when
+    /// redirect following is disabled or the redirect policy stopped.
+    #[inline]
+    pub fn is_redirect(&self) -> bool {
+        self.status.is_redirection()
+    }
+
+    /// Returns `true` if the status is a server error (`5xx`).
+    #[inline]
+    pub fn is_server_error(&self) -> bool {
+        self.status.is_server_error()
+    }
+
     /// Get the HTTP `Version` of this `Response`.
     #[inline]
     pub fn version(&self) -> Version {
@@ -116,6 +132,29 @@ impl HttpResponse {
         &mut self.headers
     }
 
+    /// Get the value of the header `name` as a string.
+    ///
+    /// The name is matched case-insensitively. Returns `None` if the header
+    /// is absent, or if its value isn't valid UTF-8. If the header appears
+    /// more than once, the first value is returned.
+    pub fn header_str(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// Get every value of the header `name` as strings, in the order they
+    /// were received.
+    ///
+    /// The name is matched case-insensitively, and values that aren't valid
+    /// UTF-8 are skipped. This is useful for headers that can appear more than
+    /// once, such as `Set-Cookie` or `Vary`.
+    pub fn all_headers_str(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect()
+    }
+
     /// Get the content-length of this response, if known.
     ///
     /// Reasons it may not be known:
@@ -128,6 +167,19 @@ impl HttpResponse {
         Some(self.body().len() as u64)
     }
 
+    /// Get how long to wait before retrying, from the `Retry-After` header.
+    ///
+    /// Both the delay-seconds form (`Retry-After: 120`) and the HTTP-date
+    /// form (`Retry-After: Wed, 21 Oct 2015 07:28:00 GMT`) are supported. A
+    /// date in the past yields a zero duration.
+    ///
+    /// Returns `None` if the header is absent or can't be parsed.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.headers
+            .typed_get::<RetryAfter>()
+            .map(|RetryAfter(delay)| delay)
+    }
+
     /// Retrieve the cookies contained in the response.
     ///
     /// Note that invalid 'Set-Cookie' headers will be ignored.
@@ -147,13 +199,25 @@ impl HttpResponse {
         &self.url
     }
 
+    /// Get an owned copy of the final `Url` of this `Response`.
+    #[inline]
+    pub fn url_cloned(&self) -> Url {
+        self.url.clone()
+    }
+
+    /// Consume the `Response`, returning its final `Url`.
+    #[inline]
+    pub fn into_url(self) -> Url {
+        self.url
+    }
+
     /// Get the remote address used to get this `Response`.
+    ///
+    /// This is the address of the proxy if the request was sent through one.
+    /// It is `None` for responses received over TLS, whose connections don't
+    /// expose their address.
     pub fn remote_addr(&self) -> Option<SocketAddr> {
-        None
-        // self.res
-        //     .extensions()
-        //     .get::<HttpInfo>()
-        //     .map(|info| info.remote_addr())
+        self.remote_addr
     }
 
     // /// Returns a reference to the associated extensions.
@@ -214,16 +278,7 @@ impl HttpResponse {
     /// # }
     /// ```
     pub fn text_with_charset(self, default_encoding: &str) -> crate::Result<String> {
-        let content_type = self
-            .headers()
-            .get(crate::header::CONTENT_TYPE)
-            .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.parse::<Mime>().ok());
-        let encoding_name = content_type
-            .as_ref()
-            .and_then(|mime| mime.get_param("charset").map(|charset| charset.as_str()))
-            .unwrap_or(default_encoding);
-        let encoding = Encoding::for_label(encoding_name.as_bytes()).unwrap_or(UTF_8);
+        let encoding = self.encoding(default_encoding);
 
         let full = self.body();
 
@@ -238,6 +293,52 @@ impl HttpResponse {
         }
     }
 
+    /// Get the full response text, never failing.
+    ///
+    /// Like [`text`](HttpResponse::text), the encoding is determined from the
+    /// `charset` parameter of the `Content-Type` header, defaulting to `utf-8`.
+    /// Malformed sequences are replaced with the REPLACEMENT CHARACTER
+    /// (U+FFFD), so this always returns a `String`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let content = nightfly::get("http://httpbin.org/range/26")?.text_lossy();
+    ///
+    /// println!("text: {}", content);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn text_lossy(self) -> String {
+        let encoding = self.encoding("utf-8");
+        encoding.decode(&self.body).0.into_owned()
+    }
+
+    /// Iterate over the lines of the response body.
+    ///
+    /// Lines are split on `\n` (with a trailing `\r` removed) without first
+    /// building the whole body as a `String`. A line that is not valid UTF-8
+    /// yields an error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let res = nightfly::get("http://httpbin.org/stream/20")?;
+    ///
+    /// for line in res.lines() {
+    ///     println!("line: {}", line?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lines(self) -> impl Iterator<Item = crate::Result<String>> {
+        BufReader::new(Cursor::new(self.body))
+            .lines()
+            .map(|line| line.map_err(crate::error::decode))
+    }
+
     /// Try to deserialize the response body as JSON.
     ///
     /// # Optional
@@ -287,6 +388,126 @@ impl HttpResponse {
         serde_json::from_slice(&full).map_err(crate::error::decode)
     }
 
+    /// Try to deserialize the response body as MessagePack.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `msgpack_serializer` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not valid MessagePack
+    /// or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "msgpack_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack_serializer")))]
+    pub fn msgpack<T: DeserializeOwned>(self) -> crate::Result<T> {
+        rmp_serde::from_slice(&self.body).map_err(crate::error::decode)
+    }
+
+    /// Try to decode the response body as a Protocol Buffers message.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `protobuf_serializer` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not a valid encoding
+    /// of the message `M`.
+    #[cfg(feature = "protobuf_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf_serializer")))]
+    pub fn protobuf<M: protobuf::Message>(self) -> crate::Result<M> {
+        M::parse_from_bytes(&self.body).map_err(crate::error::decode)
+    }
+
+    /// Try to deserialize the response body as CBOR.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cbor_serializer` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not valid CBOR
+    /// or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "cbor_serializer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor_serializer")))]
+    pub fn cbor<T: DeserializeOwned>(self) -> crate::Result<T> {
+        ciborium::de::from_reader(&self.body[..]).map_err(crate::error::decode)
+    }
+
+    /// Try to deserialize the response body as XML.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `xml` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not valid XML
+    /// or it cannot be properly deserialized to target type `T`. For more
+    /// details please see [`quick_xml::de::from_reader`].
+    ///
+    /// [`quick_xml::de::from_reader`]: https://docs.rs/quick-xml/latest/quick_xml/de/fn.from_reader.html
+    #[cfg(feature = "xml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    pub fn xml<T: DeserializeOwned>(self) -> crate::Result<T> {
+        quick_xml::de::from_reader(&self.body[..]).map_err(crate::error::decode)
+    }
+
+    /// Parse the response body as CSV, returning every record as a row of
+    /// strings.
+    ///
+    /// The parser uses the default settings of [`csv::ReaderBuilder`], so the
+    /// first line is treated as a header row and is not included.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `csv` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not valid CSV or is
+    /// not valid UTF-8.
+    ///
+    /// [`csv::ReaderBuilder`]: https://docs.rs/csv/1/csv/struct.ReaderBuilder.html
+    #[cfg(feature = "csv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
+    pub fn csv_rows(self) -> crate::Result<Vec<Vec<String>>> {
+        csv::ReaderBuilder::new()
+            .from_reader(&self.body[..])
+            .records()
+            .map(|record| {
+                record
+                    .map(|record| record.iter().map(String::from).collect())
+                    .map_err(crate::error::decode)
+            })
+            .collect()
+    }
+
+    /// Try to deserialize every CSV record of the response body as `T`.
+    ///
+    /// The first line is treated as a header row, and its names are used to
+    /// match the fields of `T`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `csv` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not valid CSV or a
+    /// record cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "csv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
+    pub fn csv_records<T: DeserializeOwned>(self) -> crate::Result<Vec<T>> {
+        csv::ReaderBuilder::new()
+            .from_reader(&self.body[..])
+            .deserialize()
+            .map(|record| record.map_err(crate::error::decode))
+            .collect()
+    }
+
     // /// Get the full response body as `Bytes`.
     // ///
     // /// # Example
@@ -397,8 +618,48 @@ impl HttpResponse {
         }
     }
 
+    /// Turn a response into an error unless it has the `expected` status.
+    ///
+    /// Unlike `error_for_status`, any other status is an error, including
+    /// other successful ones.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn run() -> Result<(), nightfly::Error> {
+    /// let client = nightfly::Client::new();
+    /// let created: serde_json::Value = client
+    ///     .post("http://httpbin.org/status/201")
+    ///     .send()?
+    ///     .assert_status(nightfly::StatusCode::CREATED)?
+    ///     .json()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn assert_status(self, expected: StatusCode) -> crate::Result<Self> {
+        if self.status == expected {
+            Ok(self)
+        } else {
+            Err(crate::error::status_code(self.url, self.status))
+        }
+    }
+
     // private
 
+    // The encoding named by the `Content-Type` charset, or `default_encoding`.
+    fn encoding(&self, default_encoding: &str) -> &'static Encoding {
+        let content_type = self
+            .headers()
+            .get(crate::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok());
+        let encoding_name = content_type
+            .as_ref()
+            .and_then(|mime| mime.get_param("charset").map(|charset| charset.as_str()))
+            .unwrap_or(default_encoding);
+        Encoding::for_label(encoding_name.as_bytes()).unwrap_or(UTF_8)
+    }
+
     // The Response's body is an implementation detail.
     // You no longer need to get a reference to it, there are async methods
     // on the `Response` itself.
@@ -410,6 +671,76 @@ impl HttpResponse {
     }
 }
 
+/// A serializable form of `HttpResponse`, used to pass responses between
+/// lunatic processes.
+///
+/// The version is kept as its name, e.g. `HTTP/1.1`, and header values as
+/// bytes, since they aren't required to be UTF-8. Converting it back fails
+/// on anything an `HttpResponse` can't hold, instead of changing it.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SerializedResponse {
+    body: Vec<u8>,
+    status: u16,
+    version: String,
+    headers: Vec<(String, Vec<u8>)>,
+    url: Url,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl From<HttpResponse> for SerializedResponse {
+    fn from(res: HttpResponse) -> SerializedResponse {
+        let headers = res
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str().to_owned(), value.as_bytes().to_vec()))
+            .collect();
+        SerializedResponse {
+            body: res.body,
+            status: res.status.as_u16(),
+            version: format!("{:?}", res.version),
+            headers,
+            url: res.url,
+            remote_addr: res.remote_addr,
+        }
+    }
+}
+
+impl TryFrom<SerializedResponse> for HttpResponse {
+    type Error = crate::Error;
+
+    fn try_from(res: SerializedResponse) -> crate::Result<HttpResponse> {
+        let version = match res.version.as_str() {
+            "HTTP/0.9" => Version::HTTP_09,
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/1.1" => Version::HTTP_11,
+            "HTTP/2.0" => Version::HTTP_2,
+            "HTTP/3.0" => Version::HTTP_3,
+            version => {
+                return Err(crate::error::decode(format!(
+                    "invalid HTTP version {:?}",
+                    version
+                )))
+            }
+        };
+        let status = StatusCode::from_u16(res.status).map_err(crate::error::decode)?;
+        let mut headers = HeaderMap::with_capacity(res.headers.len());
+        for (name, value) in res.headers {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).map_err(crate::error::decode)?,
+                HeaderValue::from_bytes(&value).map_err(crate::error::decode)?,
+            );
+        }
+        Ok(HttpResponse {
+            body: res.body,
+            status,
+            version,
+            headers,
+            url: res.url,
+            remote_addr: res.remote_addr,
+        })
+    }
+}
+
 impl fmt::Debug for HttpResponse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Response")
@@ -420,12 +751,18 @@ impl fmt::Debug for HttpResponse {
     }
 }
 
-// /// A `Response` can be piped as the `Body` of another request.
-// impl From<Response> for Body {
-//     fn from(r: Response) -> Body {
-//         Body::stream(r.res.into_body())
-//     }
-// }
+/// A `HttpResponse` can be piped as the `Body` of another request.
+impl From<HttpResponse> for Body {
+    fn from(r: HttpResponse) -> Body {
+        Body::from(r.body)
+    }
+}
+
+impl From<HttpResponse> for BodyReader {
+    fn from(r: HttpResponse) -> BodyReader {
+        BodyReader::from(Body::from(r))
+    }
+}
 
 // #[cfg(test)]
 // mod tests {
@@ -448,3 +785,148 @@ impl fmt::Debug for HttpResponse {
 //         assert_eq!(*response.url(), url);
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::header::parse_retry_after;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn delay_seconds() {
+        assert_eq!(
+            parse_retry_after("120", SystemTime::now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn http_date() {
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        let now = httpdate::parse_http_date(date).unwrap() - Duration::from_secs(30);
+        assert_eq!(parse_retry_after(date, now), Some(Duration::from_secs(30)));
+        assert_eq!(
+            parse_retry_after(date, now + Duration::from_secs(60)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn unparseable() {
+        assert_eq!(parse_retry_after("soon", SystemTime::now()), None);
+    }
+
+    #[test]
+    fn owned_url() {
+        use super::{HeaderMap, HttpResponse, StatusCode, Url, Version};
+
+        let url = Url::parse("http://localhost/final").unwrap();
+        let res = HttpResponse {
+            body: Vec::new(),
+            status: StatusCode::OK,
+            version: Version::HTTP_11,
+            headers: HeaderMap::new(),
+            url: url.clone(),
+            remote_addr: None,
+        };
+
+        assert_eq!(res.url_cloned(), url);
+        assert_eq!(res.into_url(), url);
+    }
+
+    fn response(status: super::StatusCode, headers: super::HeaderMap) -> super::HttpResponse {
+        super::HttpResponse {
+            body: Vec::new(),
+            status,
+            version: super::Version::HTTP_11,
+            headers,
+            url: super::Url::parse("http://localhost/").unwrap(),
+            remote_addr: None,
+        }
+    }
+
+    #[test]
+    fn header_str() {
+        use super::{HeaderMap, HeaderValue, StatusCode};
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("abc"));
+        headers.insert("x-binary", HeaderValue::from_bytes(b"\xff").unwrap());
+        let res = response(StatusCode::OK, headers);
+
+        assert_eq!(res.header_str("X-Request-Id"), Some("abc"));
+        assert_eq!(res.header_str("x-binary"), None);
+        assert_eq!(res.header_str("x-missing"), None);
+    }
+
+    #[test]
+    fn all_headers_str() {
+        use super::{HeaderMap, HeaderValue, StatusCode};
+
+        let mut headers = HeaderMap::new();
+        headers.append("vary", HeaderValue::from_static("accept"));
+        headers.append("vary", HeaderValue::from_bytes(b"\xff").unwrap());
+        headers.append("vary", HeaderValue::from_static("origin"));
+        let res = response(StatusCode::OK, headers);
+
+        assert_eq!(res.all_headers_str("Vary"), ["accept", "origin"]);
+        assert!(res.all_headers_str("set-cookie").is_empty());
+    }
+
+    #[test]
+    fn assert_status() {
+        use super::{HeaderMap, StatusCode};
+
+        let res = response(StatusCode::CREATED, HeaderMap::new());
+        assert!(res.assert_status(StatusCode::CREATED).is_ok());
+
+        let res = response(StatusCode::OK, HeaderMap::new());
+        let err = res.assert_status(StatusCode::CREATED).unwrap_err();
+        assert!(err.is_status());
+        assert_eq!(err.status(), Some(StatusCode::OK));
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        use super::{HeaderMap, HeaderValue, HttpResponse, StatusCode, Version};
+
+        let mut headers = HeaderMap::new();
+        headers.append("set-cookie", HeaderValue::from_static("a=1"));
+        headers.append("set-cookie", HeaderValue::from_static("b=2"));
+        headers.insert("x-binary", HeaderValue::from_bytes(b"\xff").unwrap());
+        let mut res = response(StatusCode::NOT_FOUND, headers.clone());
+        res.version = Version::HTTP_10;
+        res.body = b"missing".to_vec();
+        res.remote_addr = Some("127.0.0.1:8080".parse().unwrap());
+
+        let json = serde_json::to_vec(&res).unwrap();
+        let de: HttpResponse = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(de.status, StatusCode::NOT_FOUND);
+        assert_eq!(de.version, Version::HTTP_10);
+        assert_eq!(de.headers, headers);
+        assert_eq!(de.body, b"missing");
+        assert_eq!(de.url(), res.url());
+        assert_eq!(de.remote_addr(), res.remote_addr());
+    }
+
+    #[test]
+    fn serde_rejects_invalid_responses() {
+        use super::{HeaderMap, HttpResponse, StatusCode};
+
+        let res = response(StatusCode::OK, HeaderMap::new());
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["version"], "HTTP/1.1");
+
+        let mut invalid_status = json.clone();
+        invalid_status["status"] = 1000.into();
+        assert!(serde_json::from_value::<HttpResponse>(invalid_status).is_err());
+
+        let mut invalid_header = json.clone();
+        invalid_header["headers"] = serde_json::json!([["bad header", [97]]]);
+        assert!(serde_json::from_value::<HttpResponse>(invalid_header).is_err());
+
+        let mut invalid_version = json;
+        invalid_version["version"] = "HTTP/4".into();
+        assert!(serde_json::from_value::<HttpResponse>(invalid_version).is_err());
+    }
+}
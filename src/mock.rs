@@ -0,0 +1,120 @@
+//! Helpers for testing code that uses a `Client`.
+//!
+//! The types in this module wrap a real [`Client`](crate::Client) and are
+//! meant to be used from integration tests, so they are always available and
+//! don't require a feature to be enabled.
+
+use std::fmt;
+
+use crate::{Client, HttpResponse, IntoUrl, Method, Request, RequestBuilder};
+
+/// A `Client` wrapper that records every request it sends along with the
+/// response it received.
+///
+/// Requests are executed normally with the inner `Client`. Only successful
+/// exchanges are recorded; errors are returned to the caller untouched.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn run() -> Result<(), nightfly::Error> {
+/// use nightfly::mock::RecordingClient;
+///
+/// let mut client = RecordingClient::new(nightfly::Client::new());
+/// let req = client.get("http://httpbin.org/get").build()?;
+/// client.execute(req)?;
+///
+/// assert_eq!(client.history().len(), 1);
+/// assert_eq!(client.history()[0].0.url().path(), "/get");
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordingClient {
+    inner: Client,
+    history: Vec<(Request, HttpResponse)>,
+}
+
+impl RecordingClient {
+    /// Constructs a new `RecordingClient` wrapping the given `Client`.
+    pub fn new(inner: Client) -> RecordingClient {
+        RecordingClient {
+            inner,
+            history: Vec::new(),
+        }
+    }
+
+    /// Start building a `Request` with the `Method` and `Url`.
+    ///
+    /// The returned `RequestBuilder` sends through the inner `Client`, so
+    /// build it and pass the `Request` to `RecordingClient::execute()` to
+    /// have it recorded.
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        self.inner.request(method, url)
+    }
+
+    /// Convenience method to start building a `GET` request to a URL.
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.inner.get(url)
+    }
+
+    /// Executes a `Request` with the inner `Client` and records the exchange.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the inner `Client` fails to execute the request.
+    /// Failed requests are not recorded.
+    pub fn execute(&mut self, request: Request) -> crate::Result<HttpResponse> {
+        let recorded = request.clone();
+        let res = self.inner.execute(request)?;
+        self.history.push((recorded, res.clone()));
+        Ok(res)
+    }
+
+    /// Returns every recorded `(Request, HttpResponse)` pair, oldest first.
+    pub fn history(&self) -> &[(Request, HttpResponse)] {
+        &self.history
+    }
+
+    /// Forget all recorded exchanges.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Returns a reference to the inner `Client`.
+    pub fn inner(&self) -> &Client {
+        &self.inner
+    }
+}
+
+impl fmt::Debug for RecordingClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RecordingClient")
+            .field("inner", &self.inner)
+            .field("history", &self.history.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecordingClient;
+    use crate::Client;
+
+    #[test]
+    fn starts_empty() {
+        let client = RecordingClient::new(Client::new());
+        assert!(client.history().is_empty());
+    }
+
+    #[test]
+    fn failed_requests_are_not_recorded() {
+        let mut client = RecordingClient::new(Client::new());
+        let req = client.get("ftp://example.com").build().unwrap();
+
+        assert!(client.execute(req).is_err());
+        assert!(client.history().is_empty());
+
+        client.clear();
+        assert!(client.history().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+//! A pool of `Client` processes
+//!
+//! A `Client` lives in the process that created it. A [`Pool`] starts a fixed
+//! number of [`MailboxClient`] processes instead, and hands out their
+//! [`PoolHandle`]s round-robin, so requests from many processes are spread
+//! over the pool.
+//!
+//! The round-robin position belongs to the `Pool` value, not to its client
+//! processes. A clone of a `Pool`, including one sent to another process,
+//! keeps its own position, so several copies may hand out the same client
+//! process at the same time. To spread the requests of many processes evenly,
+//! keep the `Pool` in one process and send a [`PoolHandle`] to each of the
+//! others.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn run() -> Result<(), nightfly::Error> {
+//! use nightfly::pool::Pool;
+//!
+//! let mut pool = Pool::new(2);
+//! for _ in 0..4 {
+//!     let req = nightfly::Client::new().get("http://httpbin.org/uuid").build()?;
+//!     let res = pool.get().send(req)?;
+//!     println!("status: {}", res.status());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use lunatic::process::ProcessRef;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ClientFactory, HttpResponse, MailboxClient, MailboxClientHandler, Request, SendRequest,
+};
+
+/// A fixed-size pool of `Client` processes.
+///
+/// A `Pool` is meant to be used from a single process, see the
+/// [module documentation](crate::pool) for sharing its client processes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pool {
+    processes: Vec<ProcessRef<MailboxClient>>,
+    next: usize,
+}
+
+/// A handle to one `Client` process of a [`Pool`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolHandle {
+    process: ProcessRef<MailboxClient>,
+}
+
+impl Pool {
+    /// Start a pool of `size` client processes, linked to the calling
+    /// process, each with a `Client` of the default configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> Pool {
+        Pool::with_factory(size, ClientFactory::default())
+    }
+
+    /// Start a pool of `size` client processes like [`Pool::new`], each
+    /// building its `Client` with `factory`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn with_factory(size: usize, factory: ClientFactory) -> Pool {
+        assert!(size > 0, "a pool needs at least one client process");
+        Pool {
+            processes: (0..size)
+                .map(|_| MailboxClient::start_link_with(factory, None))
+                .collect(),
+            next: 0,
+        }
+    }
+
+    /// Get a handle to the next client process, round-robin.
+    ///
+    /// Only the handles from this `Pool` value are taken in turn; clones of it
+    /// keep their own position.
+    pub fn get(&mut self) -> PoolHandle {
+        let process = self.processes[self.next].clone();
+        self.next = (self.next + 1) % self.processes.len();
+        PoolHandle { process }
+    }
+
+    /// The number of client processes in the pool.
+    pub fn size(&self) -> usize {
+        self.processes.len()
+    }
+}
+
+impl PoolHandle {
+    /// Send a `Request` through this client process and wait for the
+    /// response.
+    ///
+    /// # Errors
+    ///
+    /// This method fails with the error of the client process if the request
+    /// could not be sent or its response could not be read.
+    pub fn send(&self, request: Request) -> crate::Result<HttpResponse> {
+        self.process.send_request(SendRequest::from(request))
+    }
+}
@@ -19,6 +19,8 @@ use crate::Url;
 ///
 /// - `limited` can be used have the same as the default behavior, but adjust
 ///   the allowed maximum redirect hops in a chain.
+/// - `limited_and_same_origin` additionally rejects redirects to a different
+///   origin than the original request.
 /// - `none` can be used to disable all redirect behavior.
 /// - `custom` can be used to create a customized policy.
 pub struct Policy {
@@ -27,6 +29,11 @@ pub struct Policy {
 
 /// A type that holds information on the next request and previous requests
 /// in redirect chain.
+///
+/// An `Attempt` is passed to the closure of [`Policy::custom`] for every
+/// redirect response, and is turned into an [`Action`] with one of
+/// [`follow`](Attempt::follow), [`stop`](Attempt::stop) or
+/// [`error`](Attempt::error).
 #[derive(Debug)]
 pub struct Attempt<'a> {
     status: StatusCode,
@@ -35,6 +42,9 @@ pub struct Attempt<'a> {
 }
 
 /// An action to perform when a redirect status code is found.
+///
+/// Actions are created from an [`Attempt`], and returned from the closure
+/// given to [`Policy::custom`] or from [`Policy::redirect`].
 #[derive(Debug)]
 pub struct Action {
     inner: ActionKind,
@@ -50,6 +60,18 @@ impl Policy {
         }
     }
 
+    /// Create a `Policy` with a maximum number of redirects, which also
+    /// refuses to leave the origin of the original request.
+    ///
+    /// Every redirect destination must have the same scheme, host and port
+    /// as the original request. An `Error` will be returned if the max is
+    /// reached or a redirect points to another origin.
+    pub fn limited_and_same_origin(max: usize) -> Self {
+        Self {
+            inner: PolicyKind::LimitSameOrigin(max),
+        }
+    }
+
     /// Create a `Policy` that does not follow any redirect.
     pub fn none() -> Self {
         Self {
@@ -134,17 +156,40 @@ impl Policy {
                     attempt.follow()
                 }
             }
+            PolicyKind::LimitSameOrigin(max) => {
+                let cross_origin = attempt
+                    .previous
+                    .first()
+                    .map_or(false, |original| original.origin() != attempt.next.origin());
+                if cross_origin {
+                    attempt.error(CrossOriginRedirect)
+                } else if attempt.previous.len() >= max {
+                    attempt.error(TooManyRedirects)
+                } else {
+                    attempt.follow()
+                }
+            }
             PolicyKind::None => attempt.stop(),
         }
     }
 
     pub(crate) fn check(&self, status: StatusCode, next: &Url, previous: &[Url]) -> ActionKind {
-        self.redirect(Attempt {
-            status,
-            next,
-            previous,
-        })
-        .inner
+        let action = self
+            .redirect(Attempt {
+                status,
+                next,
+                previous,
+            })
+            .inner;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            http.response.status_code = status.as_u16(),
+            url.full = %next,
+            http.request.resend_count = previous.len(),
+            action = ?action,
+            "redirect"
+        );
+        action
     }
 
     pub(crate) fn is_default(&self) -> bool {
@@ -174,6 +219,22 @@ impl<'a> Attempt<'a> {
     pub fn previous(&self) -> &[Url] {
         self.previous
     }
+
+    /// Get the URL of the response that is redirecting.
+    pub fn previous_url(&self) -> &Url {
+        // The chain always starts with the URL of the original request.
+        self.previous
+            .last()
+            .expect("redirect chain should contain the original URL")
+    }
+
+    /// Get the number of redirects already followed in this chain.
+    ///
+    /// This is zero for the first redirect of a request.
+    pub fn n(&self) -> usize {
+        self.previous.len().saturating_sub(1)
+    }
+
     /// Returns an action meaning nightfly should follow the next URL.
     pub fn follow(self) -> Action {
         Action {
@@ -200,9 +261,27 @@ impl<'a> Attempt<'a> {
     }
 }
 
+impl Action {
+    /// Returns true if this action follows the redirect.
+    pub fn is_follow(&self) -> bool {
+        matches!(self.inner, ActionKind::Follow)
+    }
+
+    /// Returns true if this action stops at the redirect response.
+    pub fn is_stop(&self) -> bool {
+        matches!(self.inner, ActionKind::Stop)
+    }
+
+    /// Returns true if this action fails the request with an error.
+    pub fn is_error(&self) -> bool {
+        matches!(self.inner, ActionKind::Error(_))
+    }
+}
+
 enum PolicyKind {
     Custom(Box<dyn Fn(Attempt) -> Action + Send + Sync + 'static>),
     Limit(usize),
+    LimitSameOrigin(usize),
     None,
 }
 
@@ -217,6 +296,9 @@ impl fmt::Debug for PolicyKind {
         match *self {
             PolicyKind::Custom(..) => f.pad("Custom"),
             PolicyKind::Limit(max) => f.debug_tuple("Limit").field(&max).finish(),
+            PolicyKind::LimitSameOrigin(max) => {
+                f.debug_tuple("LimitSameOrigin").field(&max).finish()
+            }
             PolicyKind::None => f.pad("None"),
         }
     }
@@ -256,6 +338,17 @@ impl fmt::Display for TooManyRedirects {
 
 impl StdError for TooManyRedirects {}
 
+#[derive(Debug)]
+struct CrossOriginRedirect;
+
+impl fmt::Display for CrossOriginRedirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cross-origin redirect")
+    }
+}
+
+impl StdError for CrossOriginRedirect {}
+
 #[test]
 fn test_redirect_policy_limit() {
     let policy = Policy::default();
@@ -289,6 +382,33 @@ fn test_redirect_policy_limit_to_0() {
     }
 }
 
+#[test]
+fn test_redirect_policy_limited_and_same_origin() {
+    let policy = Policy::limited_and_same_origin(2);
+    let previous = vec![Url::parse("http://a.b/c").unwrap()];
+
+    let next = Url::parse("http://a.b:80/d").unwrap();
+    match policy.check(StatusCode::FOUND, &next, &previous) {
+        ActionKind::Follow => (),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    for next in &["https://a.b/c", "http://x.y/c", "http://a.b:8080/c"] {
+        let next = Url::parse(next).unwrap();
+        match policy.check(StatusCode::FOUND, &next, &previous) {
+            ActionKind::Error(err) if err.is::<CrossOriginRedirect>() => (),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    let previous = vec![previous[0].clone(), next];
+    let next = Url::parse("http://a.b/e").unwrap();
+    match policy.check(StatusCode::FOUND, &next, &previous) {
+        ActionKind::Error(err) if err.is::<TooManyRedirects>() => (),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
 #[test]
 fn test_redirect_policy_custom() {
     let policy = Policy::custom(|attempt| {
@@ -312,6 +432,26 @@ fn test_redirect_policy_custom() {
     }
 }
 
+#[test]
+fn test_redirect_policy_custom_attempt() {
+    let policy = Policy::custom(|attempt| {
+        assert_eq!(attempt.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(attempt.url().as_str(), "http://a.b/d");
+        assert_eq!(attempt.previous_url().as_str(), "http://a.b/c/1");
+        assert_eq!(attempt.n(), 1);
+        attempt.follow()
+    });
+
+    let next = Url::parse("http://a.b/d").unwrap();
+    let previous = (0..2)
+        .map(|i| Url::parse(&format!("http://a.b/c/{}", i)).unwrap())
+        .collect::<Vec<_>>();
+    match policy.check(StatusCode::MOVED_PERMANENTLY, &next, &previous) {
+        ActionKind::Follow => (),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
 #[test]
 fn test_remove_sensitive_headers() {
     use http::header::{HeaderValue, ACCEPT, AUTHORIZATION, COOKIE};
@@ -335,3 +475,21 @@ fn test_remove_sensitive_headers() {
     remove_sensitive_headers(&mut headers, &next, &prev);
     assert_eq!(headers, filtered_headers);
 }
+
+#[test]
+fn test_action_accessors() {
+    let next = Url::parse("http://a.b/d").unwrap();
+    let previous = vec![Url::parse("http://a.b/c").unwrap()];
+    let attempt = || Attempt {
+        status: StatusCode::FOUND,
+        next: &next,
+        previous: &previous,
+    };
+
+    let action = attempt().follow();
+    assert!(action.is_follow() && !action.is_stop() && !action.is_error());
+    let action = attempt().stop();
+    assert!(action.is_stop() && !action.is_follow() && !action.is_error());
+    let action = attempt().error(TooManyRedirects);
+    assert!(action.is_error() && !action.is_follow() && !action.is_stop());
+}
@@ -0,0 +1,57 @@
+use http::StatusCode;
+
+/// Extension trait for `StatusCode`
+///
+/// Adds checks on how a response with a given status is handled.
+pub trait StatusCodeExt {
+    /// Returns true if a response with this status may have a body.
+    ///
+    /// Informational (`1xx`), `204 No Content` and `304 Not Modified`
+    /// responses never have one, even if they send a `Content-Length`.
+    fn allows_body(&self) -> bool;
+
+    /// Returns true if a response with this status is cacheable by default.
+    ///
+    /// These are the statuses listed as cacheable by default in
+    /// [RFC 7231, section 6.1](https://httpwg.org/specs/rfc7231.html#overview.of.status.codes):
+    /// 200, 203, 204, 206, 300, 301, 404, 405, 410, 414 and 501.
+    fn is_cacheable(&self) -> bool;
+}
+
+impl StatusCodeExt for StatusCode {
+    fn allows_body(&self) -> bool {
+        !(self.is_informational()
+            || *self == StatusCode::NO_CONTENT
+            || *self == StatusCode::NOT_MODIFIED)
+    }
+
+    fn is_cacheable(&self) -> bool {
+        matches!(
+            self.as_u16(),
+            200 | 203 | 204 | 206 | 300 | 301 | 404 | 405 | 410 | 414 | 501
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusCodeExt;
+    use http::StatusCode;
+
+    #[test]
+    fn allows_body() {
+        assert!(StatusCode::OK.allows_body());
+        assert!(StatusCode::NOT_FOUND.allows_body());
+        assert!(!StatusCode::CONTINUE.allows_body());
+        assert!(!StatusCode::NO_CONTENT.allows_body());
+        assert!(!StatusCode::NOT_MODIFIED.allows_body());
+    }
+
+    #[test]
+    fn is_cacheable() {
+        assert!(StatusCode::OK.is_cacheable());
+        assert!(StatusCode::GONE.is_cacheable());
+        assert!(!StatusCode::CREATED.is_cacheable());
+        assert!(!StatusCode::INTERNAL_SERVER_ERROR.is_cacheable());
+    }
+}
@@ -1,4 +1,65 @@
+//! Utilities
+//!
+//! Helpers for working with urls.
+
 use crate::header::{Entry, HeaderMap, OccupiedEntry};
+use crate::Url;
+
+/// Join a relative `path` onto `base`, keeping the whole path of `base`.
+///
+/// [`Url::join`] treats the last segment of a base path without a trailing
+/// slash as a file name, so joining `users` onto `http://api.example/v1`
+/// yields `http://api.example/users`. This function treats `base` as a
+/// directory instead, and a leading slash in `path` doesn't make it replace
+/// the base path either. The query and fragment of `base` are dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # fn run() -> Result<(), nightfly::Error> {
+/// use nightfly::util::url_join;
+///
+/// let base = "http://api.example/v1".parse().unwrap();
+/// assert_eq!(
+///     url_join(&base, "users")?.as_str(),
+///     "http://api.example/v1/users"
+/// );
+/// assert_eq!(
+///     url_join(&base, "/users?page=2")?.as_str(),
+///     "http://api.example/v1/users?page=2"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails if the joined url can't be parsed.
+///
+/// # Panics
+///
+/// This panics if `base` has no path to join onto, such as
+/// `mailto:user@example.com`.
+pub fn url_join(base: &Url, path: &str) -> crate::Result<Url> {
+    assert!(
+        !base.cannot_be_a_base(),
+        "url_join: `{}` has no path to join onto",
+        base
+    );
+
+    let mut base = base.clone();
+    if !base.path().ends_with('/') {
+        let dir = format!("{}/", base.path());
+        base.set_path(&dir);
+    }
+    base.join(path.trim_start_matches('/'))
+        .map_err(|e| crate::error::builder(e).with_url(base))
+}
+
+/// Encode `data` as standard, padded base64, e.g. for `Basic` credentials.
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    base64::encode(data)
+}
 
 // xor-shift
 #[cfg(not(target_arch = "wasm32"))]
@@ -65,3 +126,42 @@ pub(crate) fn replace_headers(dst: &mut HeaderMap, src: HeaderMap) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::url_join;
+    use crate::Url;
+
+    #[test]
+    fn url_join_keeps_base_path() {
+        let join = |base: &str, path: &str| {
+            url_join(&Url::parse(base).unwrap(), path)
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            join("http://a.example/v1", "users"),
+            "http://a.example/v1/users"
+        );
+        assert_eq!(
+            join("http://a.example/v1/", "users"),
+            "http://a.example/v1/users"
+        );
+        assert_eq!(
+            join("http://a.example/v1", "/users"),
+            "http://a.example/v1/users"
+        );
+        assert_eq!(join("http://a.example", "users"), "http://a.example/users");
+        assert_eq!(
+            join("http://a.example/v1?key=1#top", "users/1"),
+            "http://a.example/v1/users/1"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn url_join_without_path() {
+        let _ = url_join(&Url::parse("mailto:user@example.com").unwrap(), "users");
+    }
+}
@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Represents a version of the HTTP spec.
+///
+/// Unlike [`http::Version`], versions can be ordered, from oldest to newest:
+///
+/// ```rust
+/// use nightfly::Version;
+///
+/// assert!(Version::HTTP_10 < Version::HTTP_11);
+/// assert!(Version::HTTP_2 >= Version::HTTP_11);
+/// ```
+///
+/// It converts to and from [`http::Version`] with `From`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Version(http::Version);
+
+impl Version {
+    /// `HTTP/0.9`
+    pub const HTTP_09: Version = Version(http::Version::HTTP_09);
+    /// `HTTP/1.0`
+    pub const HTTP_10: Version = Version(http::Version::HTTP_10);
+    /// `HTTP/1.1`
+    pub const HTTP_11: Version = Version(http::Version::HTTP_11);
+    /// `HTTP/2.0`
+    pub const HTTP_2: Version = Version(http::Version::HTTP_2);
+    /// `HTTP/3.0`
+    pub const HTTP_3: Version = Version(http::Version::HTTP_3);
+
+    fn rank(&self) -> u8 {
+        match *self {
+            Version::HTTP_09 => 0,
+            Version::HTTP_10 => 1,
+            Version::HTTP_11 => 2,
+            Version::HTTP_2 => 3,
+            Version::HTTP_3 => 4,
+            // `http::Version` can't be extended outside of `http`.
+            _ => unreachable!("unknown http version"),
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl From<http::Version> for Version {
+    fn from(version: http::Version) -> Version {
+        Version(version)
+    }
+}
+
+impl From<Version> for http::Version {
+    fn from(version: Version) -> http::Version {
+        version.0
+    }
+}
+
+impl PartialEq<http::Version> for Version {
+    fn eq(&self, other: &http::Version) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Debug for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Version;
+
+    #[test]
+    fn ordering() {
+        let mut versions = vec![
+            Version::HTTP_3,
+            Version::HTTP_11,
+            Version::HTTP_09,
+            Version::HTTP_2,
+            Version::HTTP_10,
+        ];
+        versions.sort();
+
+        assert_eq!(
+            versions,
+            [
+                Version::HTTP_09,
+                Version::HTTP_10,
+                Version::HTTP_11,
+                Version::HTTP_2,
+                Version::HTTP_3,
+            ]
+        );
+    }
+
+    #[test]
+    fn http_conversion() {
+        assert_eq!(Version::from(http::Version::HTTP_2), Version::HTTP_2);
+        assert_eq!(
+            http::Version::from(Version::HTTP_10),
+            http::Version::HTTP_10
+        );
+        assert_eq!(format!("{:?}", Version::HTTP_11), "HTTP/1.1");
+        assert_eq!(Version::default(), Version::HTTP_11);
+    }
+}
@@ -84,6 +84,43 @@ fn response_text() {
     assert_eq!("Hello", text);
 }
 
+#[lunatic::test]
+fn response_text_lossy() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| async {
+        http::Response::new(vec![b'H', b'i', 0xff, b'!'].into())
+    });
+
+    let client = Client::new();
+
+    let res = client
+        .get(&format!("http://{}/text", server.addr()))
+        .send()
+        .expect("Failed to get");
+    assert_eq!("Hi\u{FFFD}!", res.text_lossy());
+}
+
+#[lunatic::test]
+fn response_lines() {
+    let _ = env_logger::try_init();
+
+    let server =
+        server::http(move |_req| async { http::Response::new("one\r\ntwo\nthree".into()) });
+
+    let client = Client::new();
+
+    let res = client
+        .get(&format!("http://{}/lines", server.addr()))
+        .send()
+        .expect("Failed to get");
+    let lines = res
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to read lines");
+    assert_eq!(lines, ["one", "two", "three"]);
+}
+
 #[lunatic::test]
 fn response_bytes() {
     let _ = env_logger::try_init();
@@ -118,6 +155,54 @@ fn response_json() {
     assert_eq!("Hello", text);
 }
 
+#[lunatic::test]
+fn head_check() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.method(), "HEAD");
+        let status = if req.uri().path() == "/up" {
+            http::StatusCode::OK
+        } else {
+            http::StatusCode::SERVICE_UNAVAILABLE
+        };
+        http::Response::builder()
+            .status(status)
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let client = Client::new();
+
+    assert!(client
+        .head_check(&format!("http://{}/up", server.addr()))
+        .unwrap());
+    assert!(!client
+        .head_check(&format!("http://{}/down", server.addr()))
+        .unwrap());
+}
+
+#[lunatic::test]
+fn get_with_headers() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.method(), "GET");
+        assert_eq!(req.headers()["x-one"], "1");
+        assert_eq!(req.headers()["x-two"], "2");
+        http::Response::default()
+    });
+
+    let mut headers = nightfly::header::HeaderMap::new();
+    headers.insert("x-one", "1".parse().unwrap());
+    headers.insert("x-two", "2".parse().unwrap());
+
+    let res = Client::new()
+        .get_with_headers(&format!("http://{}/", server.addr()), headers)
+        .unwrap();
+    assert_eq!(res.status(), nightfly::StatusCode::OK);
+}
+
 #[lunatic::test]
 fn body_pipe_response() {
     let _ = env_logger::try_init();
@@ -127,7 +212,8 @@ fn body_pipe_response() {
             http::Response::new("pipe me".into())
         } else {
             assert_eq!(req.uri(), "/pipe");
-            assert_eq!(req.headers()["transfer-encoding"], "chunked");
+            // The piped response is buffered, so it's sent with a known length.
+            assert_eq!(req.headers()["content-length"], "7");
 
             let mut full: Vec<u8> = Vec::new();
             while let Some(item) = req.body_mut().next() {
@@ -183,6 +269,90 @@ fn overridden_dns_resolution_with_gai() {
     assert_eq!("Hello", text);
 }
 
+#[lunatic::test]
+fn overridden_dns_resolution_to_unreachable_address() {
+    use std::time::{Duration, Instant};
+
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // 192.0.2.0/24 is TEST-NET-1, which is never routed (RFC 5737).
+    let client = nightfly::Client::builder()
+        .resolve_to_addrs("rust-lang.org", &["192.0.2.0:80".parse().unwrap()])
+        .connect_timeout(Duration::from_millis(100))
+        .build()
+        .expect("client builder");
+
+    let start = Instant::now();
+    let err = client
+        .get("http://rust-lang.org/domain_override")
+        .send()
+        .unwrap_err();
+
+    assert!(err.is_connect() || err.is_timeout());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[lunatic::test]
+fn process_per_request_uses_connector() {
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    let client = nightfly::Client::builder()
+        .resolve("rust-lang.org", server.addr())
+        .lunatic_process_per_request(true)
+        .build()
+        .expect("client builder");
+    let url = format!("http://rust-lang.org:{}/", server.addr().port());
+    let res = client.get(&url).send().expect("request");
+    assert_eq!(res.text().unwrap(), "Hello");
+
+    // 192.0.2.0/24 is TEST-NET-1, which is never routed (RFC 5737).
+    let client = nightfly::Client::builder()
+        .resolve_to_addrs("rust-lang.org", &["192.0.2.0:80".parse().unwrap()])
+        .connect_timeout(std::time::Duration::from_millis(100))
+        .lunatic_process_per_request(true)
+        .build()
+        .expect("client builder");
+    let err = client.get("http://rust-lang.org/").send().unwrap_err();
+    assert!(err.is_connect());
+}
+
+#[lunatic::test]
+fn send_all_uses_connector() {
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    // 192.0.2.0/24 is TEST-NET-1, which is never routed (RFC 5737).
+    let client = nightfly::Client::builder()
+        .resolve("rust-lang.org", server.addr())
+        .resolve_to_addrs("unreachable.example", &["192.0.2.0:80".parse().unwrap()])
+        .connect_timeout(std::time::Duration::from_millis(100))
+        .build()
+        .expect("client builder");
+    let url = format!("http://rust-lang.org:{}/", server.addr().port());
+    let requests = vec![
+        client.get(&url).build().unwrap(),
+        client.get("http://unreachable.example/").build().unwrap(),
+    ];
+
+    let mut results = client.send_all(requests).into_iter();
+    assert_eq!(results.next().unwrap().unwrap().text().unwrap(), "Hello");
+    assert!(results.next().unwrap().unwrap_err().is_connect());
+}
+
+#[lunatic::test]
+fn resolve_host_uses_overrides() {
+    let addr = "192.0.2.1:8080".parse().unwrap();
+    let client = nightfly::Client::builder()
+        .resolve("api.example", addr)
+        .build()
+        .expect("client builder");
+
+    assert_eq!(client.resolve_host("api.example").unwrap(), [addr]);
+    assert_eq!(
+        client.resolve_host("127.0.0.1").unwrap(),
+        ["127.0.0.1:0".parse().unwrap()]
+    );
+}
+
 #[lunatic::test]
 fn overridden_dns_resolution_with_gai_multiple() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -0,0 +1,50 @@
+//! Responses come from untrusted servers, so no input may make parsing panic.
+
+use nightfly::{fuzz_parse_response, Method};
+use proptest::prelude::*;
+
+fn method() -> impl Strategy<Value = Method> {
+    prop::sample::select(vec![Method::GET, Method::HEAD])
+}
+
+proptest! {
+    #[test]
+    fn parse_arbitrary_bytes(
+        bytes in prop::collection::vec(any::<u8>(), 0..2048),
+        split in any::<prop::sample::Index>(),
+        method in method(),
+    ) {
+        // Part of the response may already be buffered from a previous read.
+        let (buffered, stream) = bytes.split_at(split.index(bytes.len() + 1));
+        fuzz_parse_response(buffered, stream, &method);
+    }
+
+    #[test]
+    fn parse_arbitrary_head(
+        status in 0_u16..1000,
+        headers in prop::collection::vec(
+            (
+                prop::sample::select(vec![
+                    "Content-Length",
+                    "Transfer-Encoding",
+                    "Connection",
+                    "X-Other",
+                ]),
+                prop::collection::vec(0x20_u8..0x7f, 0..24),
+            ),
+            0..8,
+        ),
+        body in prop::collection::vec(any::<u8>(), 0..512),
+        method in method(),
+    ) {
+        let mut head = format!("HTTP/1.1 {} OK\r\n", status).into_bytes();
+        for (name, value) in headers {
+            head.extend(name.as_bytes());
+            head.extend(b": ");
+            head.extend(value);
+            head.extend(b"\r\n");
+        }
+        head.extend(b"\r\n");
+        fuzz_parse_response(&head, &body, &method);
+    }
+}
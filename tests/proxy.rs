@@ -4,6 +4,32 @@ use support::*;
 
 use std::env;
 
+/// Guard an environment variable, setting it to `value` until dropped and
+/// restoring the original value afterwards, even if the test panics.
+///
+/// No lock is needed: every `#[lunatic::test]` runs in its own lunatic
+/// process, with its own environment, so tests can't see each other's
+/// variables.
+fn env_guard(name: &'static str, value: impl AsRef<std::ffi::OsStr>) -> EnvGuard {
+    let orig_val = env::var_os(name);
+    env::set_var(name, value);
+    EnvGuard { name, orig_val }
+}
+
+struct EnvGuard {
+    name: &'static str,
+    orig_val: Option<std::ffi::OsString>,
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        match self.orig_val.take() {
+            Some(val) => env::set_var(self.name, val),
+            None => env::remove_var(self.name),
+        }
+    }
+}
+
 #[lunatic::test]
 fn http_proxy() {
     let url = "http://hyper.rs/prox";
@@ -29,6 +55,49 @@ fn http_proxy() {
     assert_eq!(res.status(), nightfly::StatusCode::OK);
 }
 
+#[lunatic::test]
+fn check_proxy_connectivity() {
+    let url = "http://hyper.rs/health";
+    let server = server::http(move |req| {
+        assert_eq!(req.method(), "HEAD");
+        assert_eq!(req.uri(), url);
+
+        // The length of the body a `GET` would have, which must not be read.
+        async {
+            http::Response::builder()
+                .header("content-length", "100")
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+
+    let client = nightfly::Client::builder()
+        .proxy(nightfly::Proxy::http(&format!("http://{}", server.addr())).unwrap())
+        .build()
+        .unwrap();
+    assert!(client
+        .check_proxy_connectivity(&url.parse().unwrap())
+        .unwrap());
+
+    // 192.0.2.0/24 is TEST-NET-1, which is never routed (RFC 5737).
+    let client = nightfly::Client::builder()
+        .proxy(nightfly::Proxy::http("http://192.0.2.1:8080").unwrap())
+        .connect_timeout(std::time::Duration::from_millis(100))
+        .build()
+        .unwrap();
+    assert!(!client
+        .check_proxy_connectivity(&url.parse().unwrap())
+        .unwrap());
+
+    let err = nightfly::Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .check_proxy_connectivity(&url.parse().unwrap())
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
 #[lunatic::test]
 fn http_proxy_basic_auth() {
     let url = "http://hyper.rs/prox";
@@ -106,11 +175,8 @@ fn system_http_proxy_basic_auth_parsed() {
         async { http::Response::default() }
     });
 
-    // save system setting first.
-    let system_proxy = env::var("http_proxy");
-
-    // set-up http proxy.
-    env::set_var(
+    // set-up http proxy, the original setting is restored when the guard drops.
+    let _guard = env_guard(
         "http_proxy",
         format!("http://Aladdin:open sesame@{}", server.addr()),
     );
@@ -124,12 +190,6 @@ fn system_http_proxy_basic_auth_parsed() {
 
     assert_eq!(res.url().as_str(), url);
     assert_eq!(res.status(), nightfly::StatusCode::OK);
-
-    // reset user setting.
-    match system_proxy {
-        Err(_) => env::remove_var("http_proxy"),
-        Ok(proxy) => env::set_var("http_proxy", proxy),
-    }
 }
 
 #[lunatic::test]
@@ -157,6 +217,31 @@ fn test_no_proxy() {
     assert_eq!(res.status(), nightfly::StatusCode::OK);
 }
 
+#[lunatic::test]
+fn test_proxy_from_env_disabled() {
+    let server = server::http(move |req| {
+        assert_eq!(req.method(), "GET");
+        assert_eq!(req.uri(), "/5");
+
+        async { http::Response::default() }
+    });
+    let url = format!("http://{}/5", server.addr());
+
+    // set-up an http proxy that must not be used.
+    let _guard = env_guard("http_proxy", "http://127.0.0.1:1");
+
+    let res = nightfly::Client::builder()
+        .proxy_from_env(false)
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .unwrap();
+
+    assert_eq!(res.url().as_str(), &url);
+    assert_eq!(res.status(), nightfly::StatusCode::OK);
+}
+
 #[cfg_attr(not(feature = "__internal_proxy_sys_no_cache"), ignore)]
 #[lunatic::test]
 fn test_using_system_proxy() {
@@ -172,22 +257,14 @@ fn test_using_system_proxy() {
     // Note: we're relying on the `__internal_proxy_sys_no_cache` feature to
     // check the environment every time.
 
-    // save system setting first.
-    let system_proxy = env::var("http_proxy");
-    // set-up http proxy.
-    env::set_var("http_proxy", format!("http://{}", server.addr()));
+    // set-up http proxy, the original setting is restored when the guard drops.
+    let _guard = env_guard("http_proxy", format!("http://{}", server.addr()));
 
     // system proxy is used by default
     let res = nightfly::get(url).unwrap();
 
     assert_eq!(res.url().as_str(), url);
     assert_eq!(res.status(), nightfly::StatusCode::OK);
-
-    // reset user setting.
-    match system_proxy {
-        Err(_) => env::remove_var("http_proxy"),
-        Ok(proxy) => env::set_var("http_proxy", proxy),
-    }
 }
 
 #[lunatic::test]
@@ -236,6 +236,8 @@ fn test_redirect_policy_can_stop_redirects_without_an_error() {
 
     assert_eq!(res.url().as_str(), url);
     assert_eq!(res.status(), nightfly::StatusCode::FOUND);
+    assert!(res.is_redirect());
+    assert!(!res.is_success());
 }
 
 #[lunatic::test]
@@ -59,6 +59,28 @@ fn request_timeout() {
     assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
 }
 
+#[lunatic::test]
+fn with_timeout() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |_req| {
+        async {
+            // delay returning the response
+            lunatic::time::sleep(Duration::from_secs(2));
+            http::Response::default()
+        }
+    });
+
+    let client = nightfly::Client::new().with_timeout(Duration::from_millis(500));
+
+    let url = format!("http://{}/slow", server.addr());
+
+    let err = client.get(&url).send().unwrap_err();
+
+    assert!(err.is_timeout());
+    assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[lunatic::test]
 fn connect_timeout() {
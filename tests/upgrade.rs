@@ -47,3 +47,49 @@ fn http_upgrade() {
     upgraded.read_to_end(&mut buf).unwrap();
     assert_eq!(buf, b"bar=foo");
 }
+
+#[lunatic::test]
+fn execute_with_stream() {
+    let server = server::http(move |req| {
+        assert_eq!(req.headers()["upgrade"], "foobar");
+
+        lunatic::spawn(async move {
+            let mut upgraded = hyper::upgrade::on(req).unwrap();
+
+            let mut buf = vec![0; 7];
+            upgraded.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, b"foo=bar");
+
+            upgraded.write_all(b"bar=foo").unwrap();
+        });
+
+        async {
+            http::Response::builder()
+                .status(http::StatusCode::SWITCHING_PROTOCOLS)
+                .header(http::header::CONNECTION, "upgrade")
+                .header(http::header::UPGRADE, "foobar")
+                .body(hyper::Body::empty())
+                .unwrap()
+        }
+    });
+
+    let client = nightfly::Client::new();
+    let req = client
+        .get(format!("http://{}", server.addr()))
+        .header(http::header::CONNECTION, "upgrade")
+        .header(http::header::UPGRADE, "foobar")
+        .build()
+        .unwrap();
+
+    let buf = client
+        .execute_with_stream(req, |res, mut stream| {
+            assert_eq!(res.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+            stream.write_all(b"foo=bar").unwrap();
+
+            let mut buf = vec![];
+            stream.read_to_end(&mut buf).unwrap();
+            buf
+        })
+        .unwrap();
+    assert_eq!(buf, b"bar=foo");
+}